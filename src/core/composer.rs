@@ -6,45 +6,187 @@
 //!
 
 use crate::errors::TektonError;
-use crate::utils::{hash2ordered_string, read_lines};
-use std::fs;
+use crate::models::{
+    friendly::FriendlySnippets, multiprefix_friendly::MultiPrefixTable, naming::SnippetNaming,
+    repair::PrefixRepair, sort::SortOrder,
+};
+use crate::utils::{hash2ordered_string, read_input, read_lines, restore_final_newline};
+use std::path::Path;
 
 use super::tektons::friendly_tekton::{
-    compose_friendly_snippets, read_in_json_snippets, sort_friendly_snippets,
+    build_friendly_string, compose_friendly_snippets, read_in_json_snippets, restore_field_order,
+    sort_friendly_snippets, split_by_scope,
 };
+use super::tektons::jetbrains_tekton::compose_jetbrains_templates;
+use super::tektons::jsonc_tekton::{collect_comments, restore_comments};
+use super::tektons::luasnip_tekton::compose_luasnip_snippets;
 use super::tektons::multiprefix_tekton::dynamic_prefix_combinator;
 use super::tektons::snipmate_tekton::compose_snipmate_snippets;
+use super::tektons::sublime_tekton::{compose_friendly_from_sublime, compose_sublime_snippets};
+use super::tektons::ultisnips_tekton::{
+    compose_friendly_from_ultisnips, compose_ultisnips_snippets,
+};
+use super::tektons::yasnippet_tekton::{
+    compose_friendly_from_yasnippet, compose_yasnippet_snippets,
+};
 
 /// The main snippet composition function
 ///
 /// Arguments:
 /// - `fname` is the filename of the snippets to read from
 /// - `types` is the tuple which signifies what mapping to use in the match statement
+/// - `repair` is how the snippets missing a prefix are fixed (from the answers or by prompting)
+/// - `order` is the order to write the snippets in when sorting
+/// - `naming` is how to name the Snipmate and UltiSnips snippets converted to JSON
 ///
 /// Returns:
 /// - Result of String (to write to file) or a TektonError with the reason for the error
 pub fn composer(
     fname: &String,
     types: (&str, &str),
-    interactive: bool,
+    repair: &PrefixRepair,
+    order: &SortOrder,
+    naming: &SnippetNaming,
+) -> Result<String, TektonError> {
+    compose(fname, types, repair, order, naming).map_err(|e| e.in_file(fname))
+}
+
+/// Helper for `composer`, which adds the file name to the errors
+fn compose(
+    fname: &String,
+    types: (&str, &str),
+    repair: &PrefixRepair,
+    order: &SortOrder,
+    naming: &SnippetNaming,
 ) -> Result<String, TektonError> {
-    match types {
-        ("snippet", "json") => match read_lines(fname) {
-            Ok(lines) => compose_friendly_snippets(lines),
-            Err(e) => Err(TektonError::Reason(e.to_string())),
-        },
-        ("json", "snippet") => {
-            compose_snipmate_snippets(read_in_json_snippets(fname, interactive)?)
+    match json_alias(types) {
+        ("snippet", "json") => compose_friendly_snippets(read_lines(fname)?, naming),
+        ("json", "snippet") => compose_snipmate_snippets(read_in_json_snippets(fname, repair)?),
+        ("snippets", "json") => compose_friendly_from_ultisnips(read_lines(fname)?, naming),
+        ("json", "snippets") => compose_ultisnips_snippets(read_in_json_snippets(fname, repair)?),
+        ("sublime-snippet", "json") => compose_friendly_from_sublime(fname),
+        ("json", "sublime-snippet") => {
+            let mut files = compose_sublime_snippets(read_in_json_snippets(fname, repair)?)?;
+            match files.len() {
+                1 => Ok(files.remove(0).1),
+                _ => Err(TektonError::Reason(
+                    "A `*.sublime-snippet` file holds a single snippet, convert to a directory instead"
+                        .to_string(),
+                )),
+            }
+        }
+        ("yasnippet", "json") => compose_friendly_from_yasnippet(fname),
+        ("json", "lua") => {
+            compose_luasnip_snippets(dynamic_prefix_combinator(&read_input(fname)?)?)
+        }
+        ("json", "xml") => {
+            let group = Path::new(fname)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            compose_jetbrains_templates(read_in_json_snippets(fname, repair)?, &group)
         }
         ("json", "tekton-sort") => {
-            sort_friendly_snippets(read_in_json_snippets(fname, interactive)?)
+            let sorted = sort_friendly_snippets(read_in_json_snippets(fname, repair)?, order)?;
+            let file_content = read_input(fname)?;
+            let sorted = restore_comments(
+                &restore_field_order(&sorted, &file_content)?,
+                &collect_comments(&file_content),
+            );
+            Ok(restore_final_newline(sorted, &file_content))
         }
+        (from, to) => Err(TektonError::UnsupportedMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+    }
+}
+
+/// The snippet composition function for formats that store one snippet per file
+///
+/// Arguments:
+/// - `fname` is the filename of the snippets to read from
+/// - `types` is the tuple which signifies what mapping to use in the match statement
+///
+/// Returns:
+/// - Result of the file names and contents (to write to a directory) or a TektonError with the reason for the error
+pub fn collection_composer(
+    fname: &str,
+    types: (&str, &str),
+    repair: &PrefixRepair,
+) -> Result<Vec<(String, String)>, TektonError> {
+    let files = match json_alias(types) {
+        ("json", "sublime-snippet") => {
+            compose_sublime_snippets(read_in_json_snippets(fname, repair)?)
+        }
+        ("json", "yasnippet") => compose_yasnippet_snippets(read_in_json_snippets(fname, repair)?),
+        (from, to) => Err(TektonError::UnsupportedMapping {
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+    };
+    files.map_err(|e| e.in_file(fname))
+}
+
+/// The snippet composition function that writes one output per language of the snippet `scope`
+///
+/// This is meant for the global VS Code `*.code-snippets` files, which mix the snippets of several languages.
+///
+/// Arguments:
+/// - `fname` is the filename of the snippets to read from
+/// - `types` is the tuple which signifies what mapping to use in the match statement
+///
+/// Returns:
+/// - Result of the languages (`None` for snippets without a scope) and their String (to write to file)
+///   or a TektonError with the reason for the error
+pub fn scope_composer(
+    fname: &str,
+    types: (&str, &str),
+    repair: &PrefixRepair,
+) -> Result<Vec<(Option<String>, String)>, TektonError> {
+    let outputs = match json_alias(types) {
+        ("json", to) => split_by_scope(read_in_json_snippets(fname, repair)?)
+            .into_iter()
+            .map(|(language, friendlies)| {
+                let group = language.clone().unwrap_or_default();
+                Ok((language, compose_from_friendlies(friendlies, to, &group)?))
+            })
+            .collect(),
         _ => Err(TektonError::Reason(
-            "Unsupported mapping attempted in the composer function".to_string(),
+            "Splitting by scope is only supported for JSON snippets".to_string(),
         )),
+    };
+    outputs.map_err(|e| e.in_file(fname))
+}
+
+/// Helper to write the already read in JSON snippets in one of the single file formats
+fn compose_from_friendlies(
+    friendlies: FriendlySnippets,
+    to: &str,
+    group: &str,
+) -> Result<String, TektonError> {
+    match to {
+        "json" => build_friendly_string(friendlies),
+        "snippet" => compose_snipmate_snippets(friendlies),
+        "snippets" => compose_ultisnips_snippets(friendlies),
+        "lua" => compose_luasnip_snippets(MultiPrefixTable::from(friendlies)),
+        "xml" => compose_jetbrains_templates(friendlies, group),
+        _ => Err(TektonError::UnsupportedMapping {
+            from: "json".to_string(),
+            to: to.to_string(),
+        }),
     }
 }
 
+/// Helper to treat VS Code `*.code-snippets` files as the JSON they are
+fn json_alias<'a>(types: (&'a str, &'a str)) -> (&'a str, &'a str) {
+    let alias = |filetype: &'a str| match filetype {
+        "code-snippets" => "json",
+        other => other,
+    };
+    (alias(types.0), alias(types.1))
+}
+
 /// The 'fall-back' mode for the SORT portion of the program.
 ///
 /// An 'all-else-fails try this' method to build the snippets from the file provided. This is done by
@@ -53,16 +195,161 @@ pub fn composer(
 ///
 /// Arguments:
 /// - `fname` is the filename of the snippets to read from
+/// - `order` is the order to write the snippets in
 ///
 /// Returns:
 /// - Result of String (to write to file) or a TektonError with the reason for the error
-pub fn multiprefix_composer(fname: &str) -> Result<String, TektonError> {
-    match fs::read_to_string(fname) {
-        Ok(file_content) => {
-            let snippets = dynamic_prefix_combinator(&file_content)?;
-            // order_friendly_snippets(snippets)
-            hash2ordered_string(&snippets.snippets)
+pub fn multiprefix_composer(fname: &str, order: &SortOrder) -> Result<String, TektonError> {
+    let sort = || -> Result<String, TektonError> {
+        let file_content = read_input(fname)?;
+        let snippets = dynamic_prefix_combinator(&file_content)?;
+        let sorted = restore_field_order(
+            &hash2ordered_string(&snippets.snippets, order)?,
+            &file_content,
+        )?;
+        let sorted = restore_comments(&sorted, &collect_comments(&file_content));
+        Ok(restore_final_newline(sorted, &file_content))
+    };
+    sort().map_err(|e| e.in_file(fname))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::linter::{lint_json, lint_snipmate};
+    use crate::models::format::Format;
+    use proptest::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// The conversions that read a snippet file, with the extension of the file they read
+    const FILE_MAPPINGS: [(&str, &str); 9] = [
+        ("snippet", "json"),
+        ("snippets", "json"),
+        ("sublime-snippet", "json"),
+        ("json", "snippet"),
+        ("json", "snippets"),
+        ("json", "sublime-snippet"),
+        ("json", "lua"),
+        ("json", "xml"),
+        ("json", "tekton-sort"),
+    ];
+
+    /// Text made of the pieces of every format, to get further into the readers than random bytes do
+    const SNIPPET_TEXT: &str = r#"(snippet [a-z]{0,3}( "[a-z ]{0,5}"[a-z]?)?\n|endsnippet\n|priority -?[0-9]{0,2}\n|\t[^\n]{0,8}\n|# (--|key: [a-z]{0,3}|name: [a-z]{0,3})\n|[{}\[\]",:]|"(prefix|body|description|scope)"|//|/\*|\*/|<(/?(snippet|content|tabTrigger|scope)|!\[CDATA\[)>|\]\]>|\$\{?[0-9]{0,2}[:|,]?|`|\\|[a-zé ]{0,6}|\n)*"#;
+
+    fn contents() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            any::<Vec<u8>>(),
+            any::<String>().prop_map(String::into_bytes),
+            SNIPPET_TEXT.prop_map(String::into_bytes),
+        ]
+    }
+
+    /// Helper to write the contents to a new temporary file or yasnippet directory
+    fn temp_path(extension: &str, contents: &[u8]) -> std::path::PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "tekton_fuzz_{}_{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        if extension == "yasnippet" {
+            let dir = path.join("rust-mode");
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("snippet"), contents).unwrap();
+            dir
+        } else {
+            let file = path.with_extension(extension);
+            std::fs::write(&file, contents).unwrap();
+            file
         }
-        Err(e) => Err(TektonError::Reason(e.to_string())),
+    }
+
+    proptest! {
+        #[test]
+        fn test_readers_never_panic(contents in contents()) {
+            for (from, to) in FILE_MAPPINGS {
+                let path = temp_path(from, &contents);
+                let fname = path.to_string_lossy().to_string();
+                let _ = composer(
+                    &fname,
+                    (from, to),
+                    &PrefixRepair::default(),
+                    &SortOrder::default(),
+                    &SnippetNaming::default(),
+                );
+                if from == "json" {
+                    let _ = multiprefix_composer(&fname, &SortOrder::default());
+                    let _ = collection_composer(&fname, (from, "yasnippet"), &PrefixRepair::default());
+                    let _ = scope_composer(&fname, (from, "snippets"), &PrefixRepair::default());
+                }
+                std::fs::remove_file(path).unwrap();
+            }
+
+            let path = temp_path("snippet", &contents);
+            let fname = path.to_string_lossy().to_string();
+            if let Ok(lines) = read_lines(&fname) {
+                let _ = lint_snipmate(&lines);
+            }
+            if let Ok(text) = read_input(&fname) {
+                let _ = lint_json(&text);
+                let _ = Format::sniff(&text);
+            }
+            std::fs::remove_file(path).unwrap();
+
+            let dir = temp_path("yasnippet", &contents);
+            let fname = dir.to_string_lossy().to_string();
+            let _ = composer(
+                &fname,
+                ("yasnippet", "json"),
+                &PrefixRepair::default(),
+                &SortOrder::default(),
+                &SnippetNaming::default(),
+            );
+            std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_sorting_a_sorted_file_is_byte_identical() -> Result<(), TektonError> {
+        // A string body, a single prefix, fields in any order and the final newline of an editor
+        let file = r#"{
+  "alpha": {
+    "body": "a",
+    "prefix": "a",
+    "description": "A"
+  },
+  "beta": {
+    "prefix": "b",
+    "body": [
+      "b",
+      "bb"
+    ]
+  }
+}
+"#;
+        let multi = file.replace(
+            "\"prefix\": \"b\"",
+            "\"prefix\": [\n      \"b\",\n      \"bb\"\n    ]",
+        );
+        let json = temp_path("json", file.as_bytes());
+        let multi_json = temp_path("json", multi.as_bytes());
+
+        let sorted = composer(
+            &json.to_string_lossy().to_string(),
+            ("json", "tekton-sort"),
+            &PrefixRepair::default(),
+            &SortOrder::default(),
+            &SnippetNaming::default(),
+        );
+        let multi_sorted =
+            multiprefix_composer(&multi_json.to_string_lossy(), &SortOrder::default());
+        std::fs::remove_file(json)?;
+        std::fs::remove_file(multi_json)?;
+
+        assert_eq!(sorted?, file);
+        assert_eq!(multi_sorted?, multi);
+        Ok(())
     }
 }
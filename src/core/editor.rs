@@ -0,0 +1,352 @@
+//! Functions to fix a snippet in the user's `$EDITOR` (`--editor`)
+//!
+//! The snippet is written to a temporary JSON or TOML document, which is read back once the editor is
+//! closed. A document that isn't a valid snippet is opened again with the problem written at its top.
+
+use crate::{
+    core::tektons::jsonc_tekton::parse_jsonc,
+    errors::TektonError,
+    models::{friendly::FriendlySnippetBody, repair::EditFormat},
+    utils::sanitize_filename,
+};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::Path, process::Command};
+
+/// The marker of the lines tekton adds to the document, which are removed when it is opened again
+const PROBLEM_MARKER: &str = "tekton:";
+
+/// The fields of a snippet that are edited, as a JSON document
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct JsonDocument {
+    name: String,
+    prefix: String,
+    body: Vec<String>,
+    #[serde(default)]
+    description: String,
+}
+
+/// The fields of a snippet that are edited, as a TOML document (the body is a multi-line string)
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct TomlDocument {
+    name: String,
+    prefix: String,
+    body: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// Function to fix a snippet in `$VISUAL` (or `$EDITOR`), re-opening it until it is valid
+///
+/// Arguments:
+/// - `editor`: the editor to run instead of `$VISUAL` or `$EDITOR`, if any
+/// - `name`: the name of the snippet
+/// - `snip_body`: the snippet to fix
+/// - `format`: the document to open the snippet as
+/// - `is_taken`: whether a name is already used by another snippet
+///
+/// Returns:
+/// - The (possibly renamed) snippet, or an error if the editor can't be run or the snippet was left unchanged
+pub fn edit_snippet(
+    editor: Option<&str>,
+    name: &str,
+    snip_body: FriendlySnippetBody,
+    format: EditFormat,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<(String, FriendlySnippetBody), TektonError> {
+    let editor = editor
+        .map(String::from)
+        .into_iter()
+        .chain(
+            ["VISUAL", "EDITOR"]
+                .iter()
+                .filter_map(|var| env::var(var).ok()),
+        )
+        .find(|editor| !editor.trim().is_empty())
+        .ok_or_else(|| {
+            TektonError::Reason(
+                "Set `$VISUAL` or `$EDITOR` to fix the snippets with `--editor`".to_string(),
+            )
+        })?;
+    edit_snippet_with(&editor, name, snip_body, format, is_taken)
+}
+
+/// Helper for `edit_snippet` with the editor command given (e.g. `code --wait`)
+fn edit_snippet_with(
+    editor: &str,
+    name: &str,
+    snip_body: FriendlySnippetBody,
+    format: EditFormat,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<(String, FriendlySnippetBody), TektonError> {
+    let path = env::temp_dir().join(format!(
+        "tekton-{}-{}.{}",
+        std::process::id(),
+        sanitize_filename(name),
+        format.extension()
+    ));
+    let fname = path.to_string_lossy().to_string();
+
+    let edit = || -> Result<(String, FriendlySnippetBody), TektonError> {
+        let mut document = render(name, &snip_body, format)?;
+        loop {
+            fs::write(&path, &document).map_err(|e| TektonError::from(e).in_file(&fname))?;
+            run_editor(editor, &path)?;
+            let edited =
+                fs::read_to_string(&path).map_err(|e| TektonError::from(e).in_file(&fname))?;
+            if edited == document {
+                return Err(TektonError::MissingPrefix {
+                    path: None,
+                    snippet: name.to_string(),
+                });
+            }
+            match parse(&edited, format).and_then(|fields| validate(name, fields, &is_taken)) {
+                Ok((new_name, fields)) => {
+                    let mut fixed = snip_body.clone();
+                    fixed.prefix = Some(fields.prefix);
+                    fixed.body = fields.body;
+                    fixed.description = Some(fields.description).filter(|d| !d.is_empty());
+                    return Ok((new_name, fixed));
+                }
+                Err(problem) => document = with_problem(&edited, &problem, format),
+            }
+        }
+    };
+    let result = edit();
+    let _ = fs::remove_file(&path);
+    result
+}
+
+/// Helper to run the editor on the document and wait for it to be closed
+///
+/// The editor is run by the shell like git does, so it may have arguments and quoted paths
+/// (e.g. `"/Applications/Sublime Text.app/Contents/SharedSupport/bin/subl" -w`).
+fn run_editor(editor: &str, path: &Path) -> Result<(), TektonError> {
+    #[cfg(unix)]
+    let mut command = {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{} \"$@\"", editor))
+            .arg(editor)
+            .arg(path);
+        command
+    };
+    #[cfg(not(unix))]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command
+            .arg("/C")
+            .arg(format!("{} \"{}\"", editor, path.display()));
+        command
+    };
+    let status = command
+        .status()
+        .map_err(|e| TektonError::Reason(format!("Unable to run `{}`: {}", editor, e)))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(TektonError::Reason(format!(
+            "`{}` exited with {}, the snippet was not fixed",
+            editor, status
+        ))),
+    }
+}
+
+/// Helper to write the document of a snippet, with how to use it in a comment
+fn render(
+    name: &str,
+    snip_body: &FriendlySnippetBody,
+    format: EditFormat,
+) -> Result<String, TektonError> {
+    let prefix = snip_body.prefix.clone().unwrap_or_default();
+    let description = snip_body.description.clone().unwrap_or_default();
+    let document = match format {
+        EditFormat::Json => serde_json::to_string_pretty(&JsonDocument {
+            name: name.to_string(),
+            prefix,
+            body: snip_body.body.clone(),
+            description,
+        })?,
+        EditFormat::Toml => toml::to_string(&TomlDocument {
+            name: name.to_string(),
+            prefix,
+            body: snip_body.body.join("\n"),
+            description,
+        })
+        .map_err(|e| TektonError::Reason(e.to_string()))?,
+    };
+    Ok(format!(
+        "{} Fix the snippet, then save and close the editor (closing it without changes gives up)\n{}\n",
+        comment(format),
+        document.trim_end()
+    ))
+}
+
+/// Helper to read the edited document back
+fn parse(document: &str, format: EditFormat) -> Result<JsonDocument, String> {
+    match format {
+        EditFormat::Json => parse_jsonc(document)
+            .and_then(|json| Ok(serde_json::from_value(json)?))
+            .map_err(|e| e.to_string()),
+        EditFormat::Toml => toml::from_str::<TomlDocument>(document)
+            .map(|toml| JsonDocument {
+                name: toml.name,
+                prefix: toml.prefix,
+                body: toml.body.lines().map(|line| line.to_string()).collect(),
+                description: toml.description,
+            })
+            .map_err(|e| e.message().to_string()),
+    }
+}
+
+/// Helper to check the edited snippet, returning its name and fields or the problem with it
+fn validate(
+    name: &str,
+    fields: JsonDocument,
+    is_taken: impl Fn(&str) -> bool,
+) -> Result<(String, JsonDocument), String> {
+    let new_name = fields.name.trim().to_string();
+    if new_name.is_empty() {
+        Err("the name is empty".to_string())
+    } else if new_name != name && is_taken(&new_name) {
+        Err(format!(
+            "the name `{}` is already used by another snippet",
+            new_name
+        ))
+    } else if fields.prefix.trim().is_empty() {
+        Err("the prefix is empty".to_string())
+    } else if fields.body.iter().all(|line| line.trim().is_empty()) {
+        Err("the body is empty".to_string())
+    } else {
+        Ok((new_name, fields))
+    }
+}
+
+/// Helper to put the problem with the document at its top, replacing the one from the last time
+fn with_problem(document: &str, problem: &str, format: EditFormat) -> String {
+    let marker = format!("{} {}", comment(format), PROBLEM_MARKER);
+    let problem: String = problem
+        .lines()
+        .map(|line| format!("{} {}\n", marker, line))
+        .collect();
+    let rest: String = document
+        .lines()
+        .filter(|line| !line.starts_with(&marker))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    problem + &rest
+}
+
+/// Helper to get how a line is commented out in the document
+fn comment(format: EditFormat) -> &'static str {
+    match format {
+        EditFormat::Json => "//",
+        EditFormat::Toml => "#",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet() -> FriendlySnippetBody {
+        FriendlySnippetBody::new(
+            None,
+            vec!["{{ value | downcase }}".to_string(), "\t$0".to_string()],
+            Some("String filter: downcase".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_documents_round_trip() -> Result<(), TektonError> {
+        for format in [EditFormat::Json, EditFormat::Toml] {
+            let document = render("Filter downcase", &snippet(), format)?;
+            let fields = parse(&document, format).map_err(TektonError::Reason)?;
+
+            assert_eq!(fields.name, "Filter downcase");
+            assert_eq!(fields.body, snippet().body);
+            assert_eq!(
+                validate("Filter downcase", fields, |_| false).unwrap_err(),
+                "the prefix is empty"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_problem_replaces_the_last_one() {
+        let document = "// tekton: the prefix is empty\n{\"name\": \"a\"}\n";
+
+        assert_eq!(
+            with_problem(document, "the body is empty", EditFormat::Json),
+            "// tekton: the body is empty\n{\"name\": \"a\"}\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_edit_until_valid() -> Result<(), TektonError> {
+        // The first edit breaks the document, the second one fixes it and adds the prefix
+        let script = env::temp_dir().join(format!("tekton-editor-{}.sh", std::process::id()));
+        fs::write(
+            &script,
+            "#!/bin/sh\nif grep -q 'tekton:' \"$1\"; then\n  sed -i -e 's/\"bogus\"/\"body\"/' -e 's/\"prefix\": \"\"/\"prefix\": \"dc\"/' \"$1\"\nelse\n  sed -i 's/\"body\"/\"bogus\"/' \"$1\"\nfi\n",
+        )?;
+        let editor = format!("sh {}", script.to_string_lossy());
+
+        let fixed = edit_snippet_with(
+            &editor,
+            "Filter downcase",
+            snippet(),
+            EditFormat::Json,
+            |_| false,
+        );
+        let unchanged = edit_snippet_with(
+            "true",
+            "Filter downcase",
+            snippet(),
+            EditFormat::Json,
+            |_| false,
+        );
+        fs::remove_file(&script)?;
+
+        let (name, body) = fixed?;
+        assert_eq!(name, "Filter downcase");
+        assert_eq!(body.prefix.as_deref(), Some("dc"));
+        assert_eq!(body.description, snippet().description);
+        assert!(matches!(
+            unchanged,
+            Err(TektonError::MissingPrefix { snippet, .. }) if snippet == "Filter downcase"
+        ));
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_editor_with_a_quoted_path() -> Result<(), TektonError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("tekton editor {}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let script = dir.join("fix prefix.sh");
+        fs::write(
+            &script,
+            "#!/bin/sh\n[ \"$1\" = \"-w\" ] && sed -i 's/\"prefix\": \"\"/\"prefix\": \"dc\"/' \"$2\"\n",
+        )?;
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755))?;
+        let editor = format!("\"{}\" -w", script.to_string_lossy());
+
+        let fixed = edit_snippet(
+            Some(&editor),
+            "Filter downcase",
+            snippet(),
+            EditFormat::Json,
+            |_| false,
+        );
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(fixed?.1.prefix.as_deref(), Some("dc"));
+        Ok(())
+    }
+}
@@ -1,25 +1,230 @@
 //! The entry point into the conversion of snippets
 
+use std::{collections::HashMap, path::Path};
+
 use crate::{
-    core::composer::composer,
+    core::{
+        composer::{collection_composer, composer, scope_composer},
+        tektons::{friendly_tekton::missing_prefixes, jsonc_tekton::strip_jsonc},
+    },
     errors::TektonError,
-    models::args::ConversionCommand,
-    utils::{get_filetype, write_to_file},
+    models::{
+        args::ConversionCommand, format::Format, naming::SnippetNaming, repair::PrefixRepair,
+        sort::SortOrder,
+    },
+    utils::{
+        get_dirtype, get_filetype, read_answers, read_input, sanitize_filename, write_answers,
+        write_to_dir, write_to_file, STDIO,
+    },
 };
 
-const INTERACTIVE: bool = true;
-
 /// The conversion handler ment to control the conversion portion of the program.
 pub fn convert_handler(convert: ConversionCommand) -> Result<(), TektonError> {
-    let file_extensions = (
-        get_filetype(&convert.input_filename).unwrap(),
-        get_filetype(&convert.output_filename).unwrap(),
+    let to_stdout = convert.output_filename == STDIO;
+    let output_dirtype = match to_stdout {
+        true => None,
+        false => get_dirtype(&convert.output_filename),
+    };
+    let formats = (
+        input_format(&convert.input_filename, convert.from)?,
+        output_format(&convert.output_filename, convert.to, output_dirtype)?,
     );
+    let file_extensions = (formats.0.tag(), formats.1.tag());
     let output = convert.output_filename.to_string();
-    println!("Output name: {}", output);
-    // Conversion is always interactive
-    let snippets = composer(&convert.input_filename, file_extensions, INTERACTIVE)?;
-    write_to_file(output.clone(), snippets);
-    println!("[Tekton]: Wrote snippets to {}", output);
+    // Conversion is interactive, unless the snippets come from stdin (where the answers would come from)
+    let interactive = convert.input_filename != STDIO;
+    let repair = PrefixRepair {
+        interactive,
+        answers: match &convert.answers {
+            Some(fname) => read_answers(fname)?,
+            None => HashMap::new(),
+        },
+        editor: convert.editor,
+        editor_command: None,
+    };
+    let naming = snippet_naming(&convert)?;
+    if !interactive && naming.is_interactive() {
+        return Err(TektonError::Reason(
+            "`--name-by interactive` prompts for the snippet names, so the snippets can't be read from stdin"
+                .to_string(),
+        ));
+    }
+    if to_stdout && convert.split_by_scope {
+        return Err(TektonError::Reason(
+            "`--split-by-scope` writes several files, so it can't write to stdout".to_string(),
+        ));
+    }
+    if let Some(fname) = &convert.emit_answers {
+        if formats.0 != Format::Json {
+            return Err(TektonError::Reason(
+                "`--emit-answers` lists the JSON snippets missing a prefix, so the input must be JSON"
+                    .to_string(),
+            ));
+        }
+        let missing = missing_prefixes(&read_input(&convert.input_filename)?)
+            .map_err(|e| e.in_file(&convert.input_filename))?;
+        return write_answers(fname, &repair, &missing);
+    }
+    if !to_stdout {
+        println!("Output name: {}", output);
+    }
+    if convert.split_by_scope {
+        let outputs = scope_composer(&convert.input_filename, file_extensions, &repair)?;
+        for (language, snippets) in outputs {
+            let name = match language {
+                Some(language) => Path::new(&output)
+                    .with_file_name(format!(
+                        "{}.{}",
+                        sanitize_filename(&language),
+                        get_filetype(&output).unwrap_or(file_extensions.1)
+                    ))
+                    .to_string_lossy()
+                    .to_string(),
+                None => output.clone(),
+            };
+            write_to_file(name.clone(), snippets)?;
+            println!("[Tekton]: Wrote snippets to {}", name);
+        }
+        return Ok(());
+    } else if output_dirtype.is_some() {
+        let files = collection_composer(&convert.input_filename, file_extensions, &repair)?;
+        write_to_dir(output.clone(), files)?;
+    } else {
+        let snippets = composer(
+            &convert.input_filename,
+            file_extensions,
+            &repair,
+            &SortOrder::default(),
+            &naming,
+        )?;
+        write_to_file(output.clone(), snippets)?;
+    }
+    if !to_stdout {
+        println!("[Tekton]: Wrote snippets to {}", output);
+    }
     Ok(())
 }
+
+/// Helper to get how the snippets are named from `--name-by`, `--name-template` and `--name-map`
+fn snippet_naming(convert: &ConversionCommand) -> Result<SnippetNaming, TektonError> {
+    let mapping = match &convert.name_map {
+        Some(fname) => {
+            let read = || -> Result<HashMap<String, String>, TektonError> {
+                Ok(serde_json::from_str(&strip_jsonc(&read_input(fname)?))?)
+            };
+            read().map_err(|e| e.in_file(fname))?
+        }
+        None => HashMap::new(),
+    };
+    Ok(SnippetNaming {
+        source: convert.name_by,
+        template: convert.name_template.clone(),
+        mapping,
+    })
+}
+
+/// Helper to get the format of the input
+///
+/// `--from` wins, then the directory type or extension, and then the contents of the file: files
+/// without a known extension are sniffed, as are `*.snippets` files which may be UltiSnips or Snipmate.
+///
+/// Arguments:
+/// - `fname`: the name of the input (or `-` for stdin)
+/// - `from`: the format given with `--from`
+///
+/// Returns:
+/// - The format, or a TektonError listing the supported formats when it can't be told
+fn input_format(fname: &str, from: Option<Format>) -> Result<Format, TektonError> {
+    if let Some(format) = from {
+        return Ok(format);
+    }
+    // An input is only read as a directory of snippets when it is one
+    let dirtype = match Path::new(fname).is_dir() {
+        true => get_dirtype(fname),
+        false => None,
+    };
+    let named = dirtype
+        .or_else(|| get_filetype(fname))
+        .and_then(Format::from_tag);
+    match named {
+        Some(format) if format != Format::Ultisnips => Ok(format),
+        _ => Format::sniff(&read_input(fname)?)
+            .or(named)
+            .ok_or_else(|| unknown_format(fname, "--from")),
+    }
+}
+
+/// Helper to get the format of the output, from `--to` or else the directory type or extension
+fn output_format(
+    fname: &str,
+    to: Option<Format>,
+    dirtype: Option<&str>,
+) -> Result<Format, TektonError> {
+    to.or_else(|| {
+        dirtype
+            .or_else(|| get_filetype(fname))
+            .and_then(Format::from_tag)
+    })
+    .ok_or_else(|| unknown_format(fname, "--to"))
+}
+
+/// Helper to build the error for a file whose format can't be told
+fn unknown_format(fname: &str, flag: &str) -> TektonError {
+    TektonError::Reason(format!(
+        "Unable to tell the format of `{}`, give it with `{}` (one of: {})",
+        fname,
+        flag,
+        Format::names()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_format() -> Result<(), TektonError> {
+        assert_eq!(output_format("rust.lua", None, None)?, Format::Luasnip);
+        assert_eq!(
+            output_format("rust.json", Some(Format::Ultisnips), None)?,
+            Format::Ultisnips
+        );
+        assert_eq!(
+            output_format("rust-mode/", None, Some("yasnippet"))?,
+            Format::Yasnippet
+        );
+        assert_eq!(
+            output_format("-", None, None).unwrap_err().to_string(),
+            "Unable to tell the format of `-`, give it with `--to` (one of: json, snipmate, ultisnips, luasnip, sublime, yasnippet, jetbrains)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_input_format_sniffs_contents() -> Result<(), TektonError> {
+        let dir = std::env::temp_dir().join("tekton_input_format");
+        std::fs::create_dir_all(&dir)?;
+        let snipmate = dir.join("rust.snippets");
+        let extensionless = dir.join("rust");
+        std::fs::write(&snipmate, "snippet fn A function\n\tfn ${1}() {}\n")?;
+        std::fs::write(
+            &extensionless,
+            "{ \"fn\": { \"prefix\": \"fn\", \"body\": [] } }",
+        )?;
+
+        assert_eq!(
+            input_format(&snipmate.to_string_lossy(), None)?,
+            Format::Snipmate
+        );
+        assert_eq!(
+            input_format(&extensionless.to_string_lossy(), None)?,
+            Format::Json
+        );
+        assert_eq!(
+            input_format(&extensionless.to_string_lossy(), Some(Format::Snipmate))?,
+            Format::Snipmate
+        );
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
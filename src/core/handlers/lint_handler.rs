@@ -0,0 +1,83 @@
+//! The entry point for checking snippet files for problems
+
+use crate::{
+    core::linter::{lint_json, lint_snipmate},
+    errors::TektonError,
+    models::{args::LintCommand, lint::Diagnostic},
+    utils::{crawl_files, get_filetype, read_input, read_lines},
+};
+use std::path::Path;
+
+/// The file types that can be linted
+const LINTABLE_TYPES: [&str; 3] = ["json", "code-snippets", "snippet"];
+
+/// Function to check the snippet files for problems and report them
+///
+/// A directory is crawled for the JSON and Snipmate files in it.
+///
+/// Arguments:
+/// - `lint`: the lint command from the cli
+///
+/// Returns:
+/// - An ok result if no problems were found, or a TektonError with the number of problems
+pub fn lint_handler(lint: LintCommand) -> Result<(), TektonError> {
+    let is_directory = Path::new(&lint.path).is_dir();
+
+    let mut files: Vec<String> = crawl_files(lint.path.clone(), is_directory)
+        .iter()
+        .map(|file| file.to_string_lossy().to_string())
+        .filter(|file| {
+            get_filetype(file).is_some_and(|filetype| LINTABLE_TYPES.contains(&filetype))
+        })
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        return Err(TektonError::Reason(format!(
+            "No JSON or Snipmate snippet files found at `{}`",
+            lint.path
+        )));
+    }
+
+    let mut problems: usize = 0;
+    let mut files_with_problems: usize = 0;
+    for file in files.iter() {
+        let diagnostics = match lint_file(file) {
+            Ok(diagnostics) => diagnostics,
+            Err(e) => {
+                println!("[Tekton Error]: Unable to lint file: `{}`\n\t{}", file, e);
+                problems += 1;
+                files_with_problems += 1;
+                continue;
+            }
+        };
+        for diagnostic in diagnostics.iter() {
+            println!("{}: {}", file, diagnostic.display());
+        }
+        if !diagnostics.is_empty() {
+            problems += diagnostics.len();
+            files_with_problems += 1;
+        }
+    }
+
+    match problems {
+        0 => {
+            println!("[Tekton]: No problems found in {} file(s).", files.len());
+            Ok(())
+        }
+        _ => Err(TektonError::Reason(format!(
+            "{} problem(s) found in {} of {} file(s)",
+            problems,
+            files_with_problems,
+            files.len()
+        ))),
+    }
+}
+
+/// Helper to lint a file according to its file type
+fn lint_file(file: &str) -> Result<Vec<Diagnostic>, TektonError> {
+    match get_filetype(file) {
+        Some("snippet") => Ok(lint_snipmate(&read_lines(file)?)),
+        _ => lint_json(&read_input(file)?),
+    }
+}
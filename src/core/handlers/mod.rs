@@ -1,2 +1,3 @@
 pub mod convert_handler;
+pub mod lint_handler;
 pub mod sort_handler;
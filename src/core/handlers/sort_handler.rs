@@ -1,18 +1,37 @@
-//! The entry point for sorting of snippets 
+//! The entry point for sorting of snippets
 //! which at this time assumes the sorting of JSON.
 
-use std::path::PathBuf;
+use globset::GlobSet;
+use similar::TextDiff;
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    panic,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
 
 use crate::{
-    core::composer::{composer, multiprefix_composer},
+    core::{
+        composer::{composer, multiprefix_composer},
+        tektons::friendly_tekton::missing_prefixes,
+    },
     errors::TektonError,
-    models::args::SortCommand,
-    utils::{crawl_files, get_filetype, write_to_file},
+    models::{args::SortCommand, naming::SnippetNaming, repair::PrefixRepair, sort::SortOrder},
+    utils::{
+        backup_file, build_globset, crawl_files, get_filetype, read_answers, read_input,
+        write_answers, write_to_file, STDIO,
+    },
 };
 
 // A named constant for the sort option
 const SORT_COMMAND_SLICE: &str = "tekton-sort";
 
+// The file types that are sorted when crawling a directory
+const SORTABLE_TYPES: [&str; 2] = ["json", "code-snippets"];
+
 /// Hanlder for the Sorting Mechanism
 ///
 /// Arguments
@@ -22,56 +41,223 @@ const SORT_COMMAND_SLICE: &str = "tekton-sort";
 /// - An ok result or a TektonError
 ///
 pub fn sort_handler(sort: SortCommand) -> Result<(), TektonError> {
-    let mut manager: SortConfigManager = SortConfigManager::new(sort.crawl.is_some());
-    manager.set_interactive(sort.interactive);
+    if sort.uses_legacy_arguments() {
+        eprintln!("[ WARN ]: The positional `INTERACTIVE` and `CRAWL` arguments are deprecated.\n\t  Use `--interactive` and `--recursive` instead.");
+    }
 
-    let crawled_files_and_dirs = crawl_files(sort.path, sort.crawl);
-    let filtered_files: Vec<&std::path::PathBuf> = crawled_files_and_dirs
+    let mut manager: SortConfigManager = SortConfigManager::new(sort.is_recursive());
+    manager.repair = PrefixRepair {
+        interactive: sort.is_interactive(),
+        answers: match &sort.answers {
+            Some(fname) => read_answers(fname)?,
+            None => HashMap::new(),
+        },
+        editor: sort.editor,
+        editor_command: None,
+    };
+    manager.quiet = sort.quiet;
+    manager.verbose = sort.verbose;
+    manager.backup = sort.backup;
+    manager.jobs = match manager.repair.interactive {
+        // The prompts of the files would be mixed up, so they are sorted one at a time
+        true => {
+            if sort.jobs.is_some_and(|jobs| jobs.get() > 1) {
+                println!("[ WARN ]: Interactive mode sorts one file at a time, ignoring `--jobs`.");
+            }
+            1
+        }
+        false => sort
+            .jobs
+            .or_else(|| thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get),
+    };
+    manager.order = SortOrder {
+        key: sort.by,
+        collation: sort.collation,
+        group_by: sort.group_by,
+    };
+
+    // Stdin is sorted to stdout, so nothing else is printed there (and there is no one to prompt)
+    let lists_only = sort.check || sort.diff || sort.dry_run || sort.emit_answers.is_some();
+    if sort.path == STDIO && !lists_only {
+        let sorted = sort_file(&sort.path, &manager.repair.answers_only(), &manager.order)?;
+        return write_to_file(sort.path, sorted);
+    }
+
+    let root = PathBuf::from(&sort.path);
+    if !manager.is_crawling && root.is_dir() {
+        println!(
+            "[ WARN ]: Provided a directory without crawling.\n\t  Try again with `--recursive`."
+        );
+        return Ok(());
+    }
+
+    let include = build_globset(&sort.include)?;
+    let exclude = build_globset(&sort.exclude)?;
+    let crawled_files = crawl_files(sort.path, manager.is_crawling);
+    let filtered_files: Vec<&PathBuf> = crawled_files
         .iter()
-        .filter(|buf| {
-            if buf.metadata().unwrap().is_dir() {
-                manager.is_path_directory = true;
+        .filter(|file| {
+            let sortable = get_filetype(&file.to_string_lossy())
+                .is_some_and(|filetype| SORTABLE_TYPES.contains(&filetype));
+            let selected =
+                (sortable || !manager.is_crawling) && is_selected(file, &root, &include, &exclude);
+            if !selected {
+                manager.detail(&format!("[Tekton]: Skipping `{}`", file.display()));
             }
-            !buf.metadata().unwrap().is_dir()
+            selected
         })
         .collect();
 
-    if manager.is_path_directory && !manager.is_crawling {
-        println!("[ WARN ]: Provided a directory without crawling.\n\t  Try again.");
-        return Ok(());
+    if let Some(fname) = &sort.emit_answers {
+        let mut missing: Vec<String> = Vec::new();
+        for file in filtered_files.iter() {
+            let name = file.to_string_lossy();
+            match read_input(&name).map_err(TektonError::from) {
+                Ok(contents) => match missing_prefixes(&contents) {
+                    Ok(names) => missing.extend(names),
+                    Err(e) => manager.detail(&format!(
+                        "[Tekton]: Skipping `{}`\n\t{}",
+                        name,
+                        e.in_file(&name)
+                    )),
+                },
+                Err(e) => println!("[Tekton Error]: Unable to read file: `{}`\n\t{}", name, e),
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        return write_answers(fname, &manager.repair, &missing);
     }
 
-    let files_to_correct: Vec<String> = manager.first_pass_sorting(filtered_files);
+    if sort.check || sort.diff || sort.dry_run {
+        return manager.check_sorting(filtered_files, sort.check, sort.diff);
+    }
 
-    if !manager.is_interactive {
-        if let Some(message) = manager.gen_files_to_correct_string(files_to_correct) {
-            println!("{}", message);
+    let start = Instant::now();
+    let files_to_correct: Vec<(String, TektonError)> = manager.first_pass_sorting(filtered_files);
+
+    if !manager.repair.interactive {
+        let all_sorted = files_to_correct.is_empty();
+        let names: Vec<String> = files_to_correct.into_iter().map(|(name, _)| name).collect();
+        if let Some(message) = manager.gen_files_to_correct_string(names) {
+            match all_sorted {
+                true => manager.report(&message),
+                false => println!("{}", message),
+            }
         }
     } else {
-        for name in files_to_correct.iter() {
-            let snippets = multiprefix_composer(name);
-            match snippets {
-                Ok(s) => {
-                    manager.file_count += 1;
-                    write_to_file(name.to_string(), s)
-                }
-                Err(e) => {
-                    println!(
-                        "[Tekton Error]: Unable to process file: `{}`\n\t{}",
-                        &name, e
-                    );
-                    manager.corrections_passed = false;
-                }
-            }
+        for (name, e) in files_to_correct.iter() {
+            println!(
+                "[Tekton Error]: Unable to process file: `{}`\n\t{}",
+                name, e
+            );
+            manager.corrections_passed = false;
         }
         if manager.corrections_passed {
-            println!("[Tekton]: No errors detected in the file(s).");
+            manager.report("[Tekton]: No errors detected in the file(s).");
         }
     }
 
-    println!("[Tekton]: Files sorted: {}", manager.file_count);
+    manager.print_summary(start.elapsed());
+
+    match manager.write_failures {
+        0 => Ok(()),
+        count => Err(TektonError::Reason(format!(
+            "{} file(s) could not be written",
+            count
+        ))),
+    }
+}
+
+/// Helper to run `work` on every item with up to `jobs` threads
+///
+/// The threads take the next item as they finish one, and the results are returned in the
+/// order of the items so the output doesn't depend on which thread was faster.
+fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    work: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    if jobs <= 1 || items.len() <= 1 {
+        return items.iter().map(work).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let (next, work) = (&next, &work);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(items.len()))
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut done: Vec<(usize, R)> = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(index) {
+                            Some(item) => done.push((index, work(item))),
+                            None => break done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
 
-    Ok(())
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Helper to sort a file of snippets, reading it as multi-prefix snippets if it doesn't read as the others
+///
+/// Sorting, `--check` and `--diff` all sort with this, so they agree on which files can be sorted.
+fn sort_file(
+    fname: &String,
+    repair: &PrefixRepair,
+    order: &SortOrder,
+) -> Result<String, TektonError> {
+    composer(
+        fname,
+        sort_types(fname),
+        repair,
+        order,
+        &SnippetNaming::default(),
+    )
+    .or_else(|e| multiprefix_composer(fname, order).map_err(|_| e))
+}
+
+/// Helper to get the mapping for the composer to sort a file with, stdin is always JSON
+fn sort_types(fname: &str) -> (&str, &str) {
+    match fname {
+        STDIO => ("json", SORT_COMMAND_SLICE),
+        _ => (get_filetype(fname).unwrap_or(""), SORT_COMMAND_SLICE),
+    }
+}
+
+/// Helper to check a file against the `--include` and `--exclude` globs
+///
+/// The globs are matched against the path as given, the path inside of the sorted directory
+/// and the file name, so `*.json` and `node_modules/**` work from any directory.
+fn is_selected(file: &Path, root: &Path, include: &GlobSet, exclude: &GlobSet) -> bool {
+    let candidates = [
+        Some(file),
+        file.strip_prefix(root).ok(),
+        file.file_name().map(Path::new),
+    ];
+    let matches = |globs: &GlobSet| candidates.iter().flatten().any(|c| globs.is_match(c));
+
+    (include.is_empty() || matches(include)) && !matches(exclude)
+}
+
+/// Helper to build the unified diff between the current and sorted contents of a file
+fn unified_diff(fname: &str, current: &str, sorted: &str) -> String {
+    TextDiff::from_lines(current, sorted)
+        .unified_diff()
+        .context_radius(3)
+        .header(fname, &format!("{} (sorted)", fname))
+        .to_string()
 }
 
 // The Configuration manager for the sorting
@@ -79,14 +265,26 @@ pub fn sort_handler(sort: SortCommand) -> Result<(), TektonError> {
 struct SortConfigManager {
     // Files sorted
     pub file_count: usize,
-    // Boolean for user input
-    pub is_interactive: bool,
+    // How to fix the snippets missing a prefix, with user input or the answers file
+    pub repair: PrefixRepair,
     // Boolean for recursive descent into directories
     pub is_crawling: bool,
-    // A boolean for path being a directory
-    pub is_path_directory: bool,
+    // Only print the warnings and errors
+    pub quiet: bool,
+    // Print every file as it is processed
+    pub verbose: bool,
     // Flag to determine if the corrections were successful
     pub corrections_passed: bool,
+    // The order to write the snippets in
+    pub order: SortOrder,
+    // The number of files to sort at once
+    pub jobs: usize,
+    // The files sorted and the time each one took
+    pub timings: Vec<(String, Duration)>,
+    // Boolean for keeping a copy of the files before writing them
+    pub backup: bool,
+    // The number of files that were sorted but could not be written
+    pub write_failures: usize,
 }
 
 impl SortConfigManager {
@@ -94,40 +292,167 @@ impl SortConfigManager {
     pub fn new(is_crawling: bool) -> Self {
         Self {
             file_count: 0,
-            is_interactive: false,
+            repair: PrefixRepair::default(),
             is_crawling,
-            is_path_directory: false,
+            quiet: false,
+            verbose: false,
             corrections_passed: true,
+            order: SortOrder::default(),
+            jobs: 1,
+            timings: Vec::new(),
+            backup: false,
+            write_failures: 0,
         }
     }
-    /// Method to set bool flag
-    pub fn set_interactive(&mut self, input: Option<String>) {
-        self.is_interactive = match input {
-            Some(val) => val == "yes",
-            None => false,
-        };
+
+    /// Method to write the sorted snippets over a file, keeping a backup when `--backup` was given
+    pub fn write(&self, fname: &str, snippets: String) -> Result<(), TektonError> {
+        if self.backup {
+            backup_file(fname)?;
+        }
+        write_to_file(fname.to_string(), snippets)
     }
 
-    pub fn first_pass_sorting(&mut self, filtered_files: Vec<&PathBuf>) -> Vec<String> {
-        let return_list: Vec<String> = filtered_files
+    /// Method to report a file that was sorted but could not be written
+    pub fn write_failed(&mut self, fname: &str, e: TektonError) {
+        println!("[Tekton Error]: Unable to write file: `{}`\n\t{}", fname, e);
+        self.write_failures += 1;
+    }
+    /// Method to print a message, unless `--quiet` was given
+    pub fn report(&self, message: &str) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    /// Method to print a message about a single file, only when `--verbose` was given
+    pub fn detail(&self, message: &str) {
+        if self.verbose {
+            println!("{}", message);
+        }
+    }
+
+    /// Method to print the summary of the sort, with the time of each file when `--verbose` was given
+    pub fn print_summary(&self, elapsed: Duration) {
+        for (name, time) in self.timings.iter() {
+            self.detail(&format!("[Tekton]: Sorted `{}` in {:.2?}", name, time));
+        }
+        self.report(&format!(
+            "[Tekton]: Files sorted: {} in {:.2?} ({} job(s))",
+            self.file_count, elapsed, self.jobs
+        ));
+    }
+
+    /// Function to check that the files are sorted, without writing to them
+    ///
+    /// Arguments
+    /// - `filtered_files` : the files to check
+    /// - `fail` : if the files that would change are an error (`--check`)
+    /// - `show_diff` : if a unified diff of the changes is printed for each file (`--diff`)
+    ///
+    /// Returns
+    /// - An ok result, or a TektonError with the number of files that would change when `fail` is set
+    pub fn check_sorting(
+        &self,
+        filtered_files: Vec<&PathBuf>,
+        fail: bool,
+        show_diff: bool,
+    ) -> Result<(), TektonError> {
+        let mut unsorted: Vec<String> = Vec::new();
+        let mut broken: Vec<(String, TektonError)> = Vec::new();
+
+        let names: Vec<String> = filtered_files
             .iter()
-            .filter_map(|file| {
-                let fname: String = <&std::path::PathBuf>::clone(file)
-                    .as_os_str()
-                    .to_str()
-                    .unwrap()
-                    .to_string();
-                let extensions = (get_filetype(&fname).unwrap(), SORT_COMMAND_SLICE);
-                match composer(&fname, extensions, self.is_interactive) {
-                    Ok(snippets) => {
-                        write_to_file(fname, snippets);
-                        self.file_count += 1;
-                        None
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        let outcomes = parallel_map(&names, self.jobs, |fname| {
+            let sorted = sort_file(fname, &self.repair.answers_only(), &self.order)?;
+            Ok((read_input(fname)?, sorted))
+        });
+
+        for (fname, outcome) in names.into_iter().zip(outcomes) {
+            match outcome {
+                Ok((current, sorted)) => {
+                    if current != sorted {
+                        if show_diff {
+                            print!("{}", unified_diff(&fname, &current, &sorted));
+                        }
+                        unsorted.push(fname);
+                    } else {
+                        self.detail(&format!("[Tekton]: Already sorted `{}`", fname));
                     }
-                    Err(_e) => Some(fname),
                 }
-            })
+                Err(e) => broken.push((fname, e)),
+            }
+        }
+
+        if !unsorted.is_empty() {
+            println!("[ WARN ]: These files are not sorted: ");
+            for name in unsorted.iter() {
+                println!("\t{}", name);
+            }
+        }
+        if !broken.is_empty() {
+            println!("[ WARN ]: These files could not be sorted: ");
+            for (name, e) in broken.iter() {
+                println!("\t{}\n\t\t{}", name, e);
+            }
+        }
+
+        match unsorted.len() + broken.len() {
+            count if count > 0 && !fail => {
+                self.report(&format!(
+                    "[Tekton]: Files that sorting would change: {}",
+                    count
+                ));
+                Ok(())
+            }
+            0 => {
+                self.report(&format!(
+                    "[Tekton]: Files already sorted: {}",
+                    filtered_files.len()
+                ));
+                Ok(())
+            }
+            count => Err(TektonError::Reason(format!(
+                "{} of {} file(s) failed the sort check",
+                count,
+                filtered_files.len()
+            ))),
+        }
+    }
+
+    /// Method to sort the files with `self.jobs` threads, writing the ones that could be sorted
+    ///
+    /// Returns
+    /// - The files that could not be sorted and why, in their original order
+    pub fn first_pass_sorting(
+        &mut self,
+        filtered_files: Vec<&PathBuf>,
+    ) -> Vec<(String, TektonError)> {
+        let names: Vec<String> = filtered_files
+            .iter()
+            .map(|file| file.to_string_lossy().to_string())
             .collect();
+        let manager: &Self = self;
+        let outcomes = parallel_map(&names, self.jobs, |fname| {
+            let start = Instant::now();
+            let written = sort_file(fname, &manager.repair, &manager.order)
+                .map(|snippets| manager.write(fname, snippets));
+            (written, start.elapsed())
+        });
+
+        let mut return_list: Vec<(String, TektonError)> = Vec::new();
+        for (fname, (written, elapsed)) in names.into_iter().zip(outcomes) {
+            match written {
+                Ok(Ok(())) => {
+                    self.file_count += 1;
+                    self.timings.push((fname, elapsed));
+                }
+                Ok(Err(e)) => self.write_failed(&fname, e),
+                Err(e) => return_list.push((fname, e)),
+            }
+        }
 
         return_list
     }
@@ -142,7 +467,8 @@ impl SortConfigManager {
             for name in files_to_correct.iter() {
                 error_message = error_message + "\n\t" + name;
             }
-            error_message += "\n\n[Tekton]: Run with interactive mode to be able to fix any issues in the files listed.";
+            error_message += "\n\n[Tekton]: Run with `--interactive` to be able to fix any issues in the files listed.";
+            error_message += "\n[Tekton]: Run `tekton lint <FILE>` to see what is wrong with them.";
         }
 
         if error_message.is_empty() {
@@ -152,3 +478,81 @@ impl SortConfigManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_unified_diff() {
+        let current = "{\n  \"b\": 2,\n  \"a\": 1\n}";
+        let sorted = "{\n  \"a\": 1,\n  \"b\": 2\n}";
+
+        assert_eq!(
+            unified_diff("s.json", current, sorted),
+            "--- s.json\n+++ s.json (sorted)\n@@ -1,4 +1,4 @@\n {\n-  \"b\": 2,\n-  \"a\": 1\n+  \"a\": 1,\n+  \"b\": 2\n }\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() -> Result<(), TektonError> {
+        let root = Path::new("snippets");
+        let include = build_globset(&["*.json".to_string()])?;
+        let exclude = build_globset(&["node_modules/**".to_string()])?;
+        let everything = build_globset(&[])?;
+
+        let selected = |file: &str| is_selected(Path::new(file), root, &include, &exclude);
+
+        assert!(selected("snippets/rust.json"));
+        assert!(selected("snippets/nested/go.json"));
+        assert!(!selected("snippets/lua.lua"));
+        assert!(!selected("snippets/node_modules/pkg/package.json"));
+        assert!(is_selected(
+            Path::new("snippets/lua.lua"),
+            root,
+            &everything,
+            &everything
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let work = |item: &u64| {
+            // The first items are the slowest, so they finish last
+            thread::sleep(Duration::from_micros(50 - item));
+            item * 2
+        };
+
+        let expected: Vec<u64> = items.iter().map(|item| item * 2).collect();
+        assert_eq!(parallel_map(&items, 4, work), expected);
+        assert_eq!(parallel_map(&items, 1, work), expected);
+    }
+
+    #[test]
+    fn test_check_and_sort_agree_on_multi_prefix_files() -> Result<(), TektonError> {
+        // Sorted, with several prefixes and the final newline an editor leaves
+        let file = std::env::temp_dir().join("tekton_check_multi_prefix.json");
+        let content = "{\n  \"alpha\": {\n    \"prefix\": [\n      \"a\",\n      \"aa\"\n    ],\n    \"body\": [\n      \"a\"\n    ]\n  }\n}\n";
+        fs::write(&file, content)?;
+
+        let mut manager = SortConfigManager::new(false);
+        manager.quiet = true;
+        let checked = manager.check_sorting(vec![&file], true, false);
+        let failed = manager.first_pass_sorting(vec![&file]);
+        let written = fs::read_to_string(&file)?;
+        fs::remove_file(&file)?;
+
+        assert!(checked.is_ok());
+        assert!(failed.is_empty());
+        assert_eq!(written, content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        assert!(build_globset(&["snippets/[".to_string()]).is_err());
+    }
+}
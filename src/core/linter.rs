@@ -0,0 +1,359 @@
+//! Functions to check snippet files for problems (`tekton lint`)
+//!
+//! The rules work on the snippets as written, so the Snipmate files are read here instead of with
+//! `build_snippets_from_file` (which replaces the tabs of the body).
+
+use crate::{
+    core::{
+        parser::{parse_body, parse_snippet},
+        tektons::{friendly_tekton::retrieve_body, jsonc_tekton::parse_jsonc},
+    },
+    errors::TektonError,
+    models::{
+        ast::Marker,
+        lint::{Diagnostic, LintRule},
+    },
+};
+use regex::Regex;
+use std::collections::HashMap;
+
+/// A snippet as seen by the linter
+struct LintSnippet {
+    name: String,
+    prefixes: Vec<String>,
+    body: Vec<String>,
+    description: Option<String>,
+}
+
+/// Function to lint a JSON (or JSONC) snippet file
+///
+/// Arguments:
+/// - `contents`: the contents of the file
+///
+/// Returns:
+/// - The problems found, in the order of the snippets, or an error if the file isn't valid JSON
+pub fn lint_json(contents: &str) -> Result<Vec<Diagnostic>, TektonError> {
+    let json = parse_jsonc(contents)?;
+    let table = json
+        .as_object()
+        .ok_or_else(|| TektonError::Reason("Expected a table of snippets".to_string()))?;
+
+    let snippets: Vec<LintSnippet> = table
+        .iter()
+        .map(|(name, v)| {
+            let prefixes = match &v["prefix"] {
+                serde_json::Value::Array(prefixes) => prefixes
+                    .iter()
+                    .filter_map(|prefix| prefix.as_str().map(|prefix| prefix.to_string()))
+                    .collect(),
+                prefix => prefix.as_str().map(|p| p.to_string()).into_iter().collect(),
+            };
+            Ok(LintSnippet {
+                name: name.to_string(),
+                prefixes,
+                body: retrieve_body(name, &v["body"])?,
+                description: v["description"].as_str().map(|d| d.to_string()),
+            })
+        })
+        .collect::<Result<_, TektonError>>()?;
+
+    Ok(lint_snippets(&snippets))
+}
+
+/// Function to lint a Snipmate snippet file
+///
+/// Arguments:
+/// - `lines`: the lines of the file
+///
+/// Returns:
+/// - The problems found, in the order of the snippets
+pub fn lint_snipmate(lines: &[String]) -> Vec<Diagnostic> {
+    let mut snippets: Vec<LintSnippet> = Vec::new();
+    for line in lines {
+        if let Some(header) = line.strip_prefix("snippet ") {
+            let mut words = header.split_whitespace();
+            let prefix = words.next().unwrap_or("").to_string();
+            let description = words.collect::<Vec<&str>>().join(" ").replace('"', "");
+            snippets.push(LintSnippet {
+                name: prefix.clone(),
+                prefixes: vec![prefix],
+                body: Vec::new(),
+                description: Some(description).filter(|d| !d.is_empty()),
+            });
+        } else if let (Some(snippet), Some(body_line)) =
+            (snippets.last_mut(), line.strip_prefix('\t'))
+        {
+            snippet.body.push(body_line.to_string());
+        }
+    }
+
+    lint_snippets(&snippets)
+}
+
+/// Helper to run every rule on the snippets of a file
+fn lint_snippets(snippets: &[LintSnippet]) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut prefixes: HashMap<&str, &str> = HashMap::new();
+
+    for snippet in snippets {
+        let name = &snippet.name;
+        for prefix in snippet.prefixes.iter() {
+            match prefixes.get(prefix.as_str()) {
+                Some(other) => diagnostics.push(Diagnostic::new(
+                    name,
+                    LintRule::DuplicatePrefix,
+                    format!("prefix `{}` is also used by `{}`", prefix, other),
+                )),
+                None => {
+                    prefixes.insert(prefix, name);
+                }
+            }
+        }
+
+        if snippet.body.iter().all(|line| line.trim().is_empty()) {
+            diagnostics.push(Diagnostic::new(
+                name,
+                LintRule::EmptyBody,
+                "the body is empty".to_string(),
+            ));
+        }
+        if snippet
+            .description
+            .as_deref()
+            .unwrap_or("")
+            .trim()
+            .is_empty()
+        {
+            diagnostics.push(Diagnostic::new(
+                name,
+                LintRule::MissingDescription,
+                "the snippet has no description".to_string(),
+            ));
+        }
+
+        let messages = [
+            (LintRule::UnbalancedBraces, check_braces(&snippet.body)),
+            (LintRule::TabstopGap, check_tabstops(&snippet.body)),
+            (LintRule::InvalidChoice, check_choices(&snippet.body)),
+            (LintRule::MixedIndentation, check_indentation(&snippet.body)),
+        ];
+        for (rule, found) in messages {
+            for message in found {
+                diagnostics.push(Diagnostic::new(name, rule, message));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Helper to find the `${` that are never closed
+///
+/// The plain braces of the body (e.g. `fn x() {}`) are tracked too, so their `}` doesn't close a `${`.
+fn check_braces(body: &[String]) -> Vec<String> {
+    // The open braces, with the body line of each `${` (`None` for a plain `{`)
+    let mut open: Vec<Option<usize>> = Vec::new();
+    for (number, line) in body.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut index = 0;
+        while index < chars.len() {
+            match (chars[index], chars.get(index + 1)) {
+                ('\\', _) => index += 1,
+                ('$', Some('{')) => {
+                    open.push(Some(number + 1));
+                    index += 1;
+                }
+                ('{', _) => open.push(None),
+                ('}', _) => {
+                    open.pop();
+                }
+                _ => {}
+            }
+            index += 1;
+        }
+    }
+
+    open.iter()
+        .flatten()
+        .map(|line| format!("a `${{` on body line {} is never closed", line))
+        .collect()
+}
+
+/// Helper to find the numbers skipped by the tabstops (`$0` is the final cursor and isn't counted)
+fn check_tabstops(body: &[String]) -> Vec<String> {
+    let tabstops: Vec<usize> = parse_body(body)
+        .tabstops()
+        .into_iter()
+        .filter(|index| *index != 0)
+        .collect();
+    let last = tabstops.last().copied().unwrap_or(0);
+
+    (1..last)
+        .filter(|index| !tabstops.contains(index))
+        .map(|index| {
+            format!(
+                "tabstop ${} is missing (the body goes up to ${})",
+                index, last
+            )
+        })
+        .collect()
+}
+
+/// Helper to find the choices that don't parse or have empty options
+fn check_choices(body: &[String]) -> Vec<String> {
+    let choice = Regex::new(r"\$\{\d+\|").unwrap();
+    let mut messages: Vec<String> = Vec::new();
+    for (number, line) in body.iter().enumerate() {
+        for found in choice.find_iter(line) {
+            if line[..found.start()].ends_with('\\') {
+                continue;
+            }
+            // Choices are expected to fit on their line, so the rest of the line holds all of it
+            match parse_snippet(&line[found.start()..]).markers.first() {
+                Some(Marker::Choice { options, .. }) => {
+                    if options.iter().any(|option| option.is_empty()) {
+                        messages.push(format!(
+                            "the choice on body line {} has an empty option",
+                            number + 1
+                        ));
+                    }
+                }
+                _ => messages.push(format!(
+                    "the choice on body line {} is not closed with `|}}`",
+                    number + 1
+                )),
+            }
+        }
+    }
+    messages
+}
+
+/// Helper to find body lines indented with both tabs and spaces
+fn check_indentation(body: &[String]) -> Vec<String> {
+    let mut tabs: Option<usize> = None;
+    let mut spaces: Option<usize> = None;
+    for (number, line) in body.iter().enumerate() {
+        let indent: String = line
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        if indent.contains(' ') && indent.contains('\t') {
+            return vec![format!(
+                "body line {} is indented with both tabs and spaces",
+                number + 1
+            )];
+        }
+        if indent.starts_with('\t') {
+            tabs.get_or_insert(number + 1);
+        } else if indent.starts_with(' ') {
+            spaces.get_or_insert(number + 1);
+        }
+    }
+
+    match (tabs, spaces) {
+        (Some(tab_line), Some(space_line)) => vec![format!(
+            "body line {} is indented with tabs but body line {} with spaces",
+            tab_line, space_line
+        )],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(diagnostics: &[Diagnostic]) -> Vec<(&str, LintRule)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.snippet.as_str(), diagnostic.rule))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_json() -> Result<(), TektonError> {
+        let file = r#"{
+            "print": {
+                "prefix": "pr",
+                "body": ["print(${1:value})", "\t${2|a,b|}$0"],
+                "description": "Print a value"
+            }
+        }"#;
+
+        assert_eq!(lint_json(file)?, Vec::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lint_json() -> Result<(), TektonError> {
+        let file = r#"{
+            "alpha": { "prefix": ["a", "al"], "body": ["${1:x} $3 ${4|a,,b|}"], "description": "A" },
+            "beta": { "prefix": "a", "body": [""] },
+            "gamma": { "prefix": "g", "body": ["${1:open", "${2|a,b}"], "description": "G" },
+            "delta": { "prefix": "d", "body": ["\tone", "  two"], "description": "D" }
+          }"#;
+
+        let diagnostics = lint_json(file)?;
+
+        assert_eq!(
+            rules(&diagnostics),
+            vec![
+                ("alpha", LintRule::TabstopGap),
+                ("alpha", LintRule::InvalidChoice),
+                ("beta", LintRule::DuplicatePrefix),
+                ("beta", LintRule::EmptyBody),
+                ("beta", LintRule::MissingDescription),
+                ("gamma", LintRule::UnbalancedBraces),
+                ("gamma", LintRule::InvalidChoice),
+                ("delta", LintRule::MixedIndentation),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "tabstop $2 is missing (the body goes up to $4)"
+        );
+        assert_eq!(diagnostics[2].message, "prefix `a` is also used by `alpha`");
+        assert_eq!(
+            diagnostics[6].message,
+            "the choice on body line 2 is not closed with `|}`"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_braces_ignores_plain_braces() {
+        let body =
+            |lines: &[&str]| -> Vec<String> { lines.iter().map(|l| l.to_string()).collect() };
+
+        assert_eq!(
+            check_braces(&body(&["${1:fn x() {}", "\t$0"])),
+            vec!["a `${` on body line 1 is never closed"]
+        );
+        assert!(check_braces(&body(&["fn ${1:x}() {", "\t${2:body}", "}"])).is_empty());
+    }
+
+    #[test]
+    fn test_lint_snipmate() {
+        let lines: Vec<String> = [
+            "snippet for \"A for loop\"",
+            "\tfor ${1:i} in $2 {",
+            "\t \t$0",
+            "\t}",
+            "snippet for",
+            "\t",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        assert_eq!(
+            rules(&lint_snipmate(&lines)),
+            vec![
+                ("for", LintRule::MixedIndentation),
+                ("for", LintRule::DuplicatePrefix),
+                ("for", LintRule::EmptyBody),
+                ("for", LintRule::MissingDescription),
+            ]
+        );
+    }
+}
@@ -1,4 +1,7 @@
-//! The main module with functions to parse, convert, and sort snippets
+//! The main module with functions to parse, convert, sort, and lint snippets
 pub mod composer;
+pub mod editor;
 pub mod handlers;
+pub mod linter;
+pub mod parser;
 pub mod tektons;
@@ -0,0 +1,634 @@
+//! Functions to parse snippet bodies (the LSP/TextMate snippet grammar) into a syntax tree
+//!
+//! The grammar is the one VS Code uses: tabstops (`$1`, `${1}`), placeholders (`${1:default}`),
+//! choices (`${1|a,b|}`), variables (`$NAME`, `${NAME:default}`) and transforms
+//! (`${1/regex/format/options}`). Like VS Code, anything that doesn't parse is kept as plain text.
+
+use crate::models::ast::{FormatItem, Marker, SnippetAst, Transform};
+use std::collections::HashMap;
+
+/// Function to parse the lines of a snippet body
+///
+/// Arguments:
+/// - `body`: the lines of the body, which are joined with newlines since placeholders may span lines
+///
+/// Returns:
+/// - The syntax tree of the body
+pub fn parse_body(body: &[String]) -> SnippetAst {
+    parse_snippet(&body.join("\n"))
+}
+
+/// Function to parse a snippet body
+///
+/// The body is read in one pass, keeping the placeholders that are still open on a stack, so the time
+/// taken grows linearly with the body even when its placeholders are never closed.
+///
+/// Arguments:
+/// - `body`: the snippet body as a single string
+///
+/// Returns:
+/// - The syntax tree of the body
+pub fn parse_snippet(body: &str) -> SnippetAst {
+    let mut parser = Parser {
+        chars: body.chars().collect(),
+        index: 0,
+        unclosed: HashMap::new(),
+    };
+    SnippetAst {
+        markers: parser.markers(),
+    }
+}
+
+/// The state of the parser, a position in the characters of the body
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    /// The first position from which the conditional text ending with a character runs to the end of
+    /// the body, so it isn't read again from every later position
+    unclosed: HashMap<char, usize>,
+}
+
+/// The markers read so far at one level of the body (the top level, or the default of a placeholder)
+#[derive(Default)]
+struct Level {
+    markers: Vec<Marker>,
+    text: String,
+}
+
+impl Level {
+    /// Helper to add a marker after the text read so far
+    fn push(&mut self, marker: Marker) {
+        match marker {
+            Marker::Text(text) => self.text.push_str(&text),
+            marker => {
+                if !self.text.is_empty() {
+                    self.markers
+                        .push(Marker::Text(std::mem::take(&mut self.text)));
+                }
+                self.markers.push(marker);
+            }
+        }
+    }
+
+    /// Helper to get the markers of the level, with the text read last
+    fn finish(mut self) -> Vec<Marker> {
+        if !self.text.is_empty() {
+            self.markers.push(Marker::Text(self.text));
+        }
+        self.markers
+    }
+}
+
+/// A placeholder or variable whose default is being read, waiting for its closing `}`
+struct Open {
+    /// The characters opening it (e.g. `${1:`), which are kept as text if it is never closed
+    opening: String,
+    /// The placeholder or variable, with an empty default
+    marker: Marker,
+    /// Its default read so far
+    level: Level,
+}
+
+/// What starts at a `$`
+enum Dollar {
+    /// A whole marker
+    Marker(Marker),
+    /// A placeholder or variable with a default (`${1:` or `${NAME:`), which is closed later
+    Open(Marker),
+}
+
+impl Parser {
+    /// Helper to look at the character `offset` characters ahead
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.index + offset).copied()
+    }
+
+    /// Helper to consume the character if it is the expected one
+    fn accept(&mut self, expected: char) -> bool {
+        let accepted = self.peek(0) == Some(expected);
+        if accepted {
+            self.index += 1;
+        }
+        accepted
+    }
+
+    /// Helper to consume characters while they match the predicate
+    fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek(0).filter(|c| predicate(*c)) {
+            text.push(c);
+            self.index += 1;
+        }
+        text
+    }
+
+    /// Helper to read a tabstop or group number
+    fn number(&mut self) -> Option<usize> {
+        self.read_while(|c| c.is_ascii_digit()).parse().ok()
+    }
+
+    /// Helper to read a variable name
+    fn name(&mut self) -> Option<String> {
+        match self.peek(0) {
+            Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+                Some(self.read_while(|c| c == '_' || c.is_ascii_alphanumeric()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads the markers of the whole body
+    ///
+    /// A `}` closes the placeholder opened last, and the placeholders still open at the end of the body
+    /// are kept as text (their defaults are read the same way at any level).
+    fn markers(&mut self) -> Vec<Marker> {
+        let mut top = Level::default();
+        let mut open: Vec<Open> = Vec::new();
+
+        while let Some(c) = self.peek(0) {
+            if c == '\\' && matches!(self.peek(1), Some('$' | '}' | '\\')) {
+                let level = open.last_mut().map_or(&mut top, |open| &mut open.level);
+                level.text.extend(self.peek(1));
+                self.index += 2;
+                continue;
+            }
+            if c == '}' {
+                if let Some(closed) = open.pop() {
+                    self.index += 1;
+                    let level = open.last_mut().map_or(&mut top, |open| &mut open.level);
+                    level.push(with_default(closed.marker, closed.level.finish()));
+                    continue;
+                }
+            }
+            if c == '$' {
+                let start = self.index;
+                match self.dollar() {
+                    Some(Dollar::Marker(marker)) => {
+                        let level = open.last_mut().map_or(&mut top, |open| &mut open.level);
+                        level.push(marker);
+                        continue;
+                    }
+                    Some(Dollar::Open(marker)) => {
+                        open.push(Open {
+                            opening: self.chars[start..self.index].iter().collect(),
+                            marker,
+                            level: Level::default(),
+                        });
+                        continue;
+                    }
+                    None => self.index = start,
+                }
+            }
+            let level = open.last_mut().map_or(&mut top, |open| &mut open.level);
+            level.text.push(c);
+            self.index += 1;
+        }
+
+        while let Some(unclosed) = open.pop() {
+            let level = open.last_mut().map_or(&mut top, |open| &mut open.level);
+            level.text.push_str(&unclosed.opening);
+            for marker in unclosed.level.finish() {
+                level.push(marker);
+            }
+        }
+        top.finish()
+    }
+
+    /// Reads what starts at a `$`, or None (leaving the position anywhere) if it isn't a marker
+    fn dollar(&mut self) -> Option<Dollar> {
+        self.index += 1;
+        if let Some(index) = self.number() {
+            return Some(Dollar::Marker(Marker::Tabstop {
+                index,
+                transform: None,
+            }));
+        }
+        if let Some(name) = self.name() {
+            return Some(Dollar::Marker(Marker::Variable {
+                name,
+                default: None,
+                transform: None,
+            }));
+        }
+        if !self.accept('{') {
+            return None;
+        }
+
+        if let Some(index) = self.number() {
+            let marker = match self.peek(0)? {
+                '}' => Marker::Tabstop {
+                    index,
+                    transform: None,
+                },
+                ':' => {
+                    self.index += 1;
+                    return Some(Dollar::Open(Marker::Placeholder {
+                        index,
+                        children: Vec::new(),
+                    }));
+                }
+                '|' => {
+                    self.index += 1;
+                    let options = self.choices()?;
+                    return Some(Dollar::Marker(Marker::Choice { index, options }));
+                }
+                '/' => {
+                    self.index += 1;
+                    let transform = Some(self.transform()?);
+                    Marker::Tabstop { index, transform }
+                }
+                _ => return None,
+            };
+            return self.accept('}').then_some(Dollar::Marker(marker));
+        }
+
+        let name = self.name()?;
+        let marker = match self.peek(0)? {
+            '}' => Marker::Variable {
+                name,
+                default: None,
+                transform: None,
+            },
+            ':' => {
+                self.index += 1;
+                return Some(Dollar::Open(Marker::Variable {
+                    name,
+                    default: Some(Vec::new()),
+                    transform: None,
+                }));
+            }
+            '/' => {
+                self.index += 1;
+                let transform = Some(self.transform()?);
+                Marker::Variable {
+                    name,
+                    default: None,
+                    transform,
+                }
+            }
+            _ => return None,
+        };
+        self.accept('}').then_some(Dollar::Marker(marker))
+    }
+
+    /// Reads the options of a choice, up to (and consuming) the closing `|}`
+    fn choices(&mut self) -> Option<Vec<String>> {
+        let mut options: Vec<String> = vec![String::new()];
+        loop {
+            match self.peek(0)? {
+                '\\' if matches!(self.peek(1), Some('$' | '}' | '\\' | ',' | '|')) => {
+                    options.last_mut()?.extend(self.peek(1));
+                    self.index += 2;
+                }
+                ',' => {
+                    options.push(String::new());
+                    self.index += 1;
+                }
+                '|' => {
+                    self.index += 1;
+                    return self.accept('}').then_some(options);
+                }
+                c => {
+                    options.last_mut()?.push(c);
+                    self.index += 1;
+                }
+            }
+        }
+    }
+
+    /// Reads a transform after its first `/`, up to (not consuming) the closing `}`
+    fn transform(&mut self) -> Option<Transform> {
+        let mut regex = String::new();
+        loop {
+            match self.peek(0)? {
+                '\\' if self.peek(1) == Some('/') => {
+                    regex.push('/');
+                    self.index += 2;
+                }
+                '\\' => {
+                    regex.push('\\');
+                    regex.extend(self.peek(1));
+                    self.index += 2;
+                }
+                '/' => {
+                    self.index += 1;
+                    break;
+                }
+                c => {
+                    regex.push(c);
+                    self.index += 1;
+                }
+            }
+        }
+
+        let mut format: Vec<FormatItem> = Vec::new();
+        let mut text = String::new();
+        loop {
+            match self.peek(0)? {
+                '\\' if matches!(self.peek(1), Some('$' | '}' | '\\' | '/')) => {
+                    text.extend(self.peek(1));
+                    self.index += 2;
+                }
+                '/' => {
+                    self.index += 1;
+                    break;
+                }
+                '$' => {
+                    let start = self.index;
+                    match self.format_item() {
+                        Some(item) => {
+                            if !text.is_empty() {
+                                format.push(FormatItem::Text(std::mem::take(&mut text)));
+                            }
+                            format.push(item);
+                        }
+                        None => {
+                            self.index = start + 1;
+                            text.push('$');
+                        }
+                    }
+                }
+                c => {
+                    text.push(c);
+                    self.index += 1;
+                }
+            }
+        }
+        if !text.is_empty() {
+            format.push(FormatItem::Text(text));
+        }
+
+        let options = self.read_while(|c| c.is_ascii_alphabetic());
+        Some(Transform {
+            regex,
+            format,
+            options,
+        })
+    }
+
+    /// Reads the format item starting at a `$`, or None if it isn't one
+    fn format_item(&mut self) -> Option<FormatItem> {
+        self.index += 1;
+        if let Some(group) = self.number() {
+            return Some(FormatItem::Group(group));
+        }
+        if !self.accept('{') {
+            return None;
+        }
+        let group = self.number()?;
+        if self.accept('}') {
+            return Some(FormatItem::Group(group));
+        }
+        if !self.accept(':') {
+            return None;
+        }
+
+        let item = if self.accept('/') {
+            let modifier = self.read_while(|c| c.is_ascii_alphabetic());
+            FormatItem::Modifier { group, modifier }
+        } else if self.accept('+') {
+            FormatItem::Conditional {
+                group,
+                if_value: Some(self.conditional_text('}')?),
+                else_value: None,
+            }
+        } else if self.accept('?') {
+            let if_value = Some(self.conditional_text(':')?);
+            self.accept(':');
+            FormatItem::Conditional {
+                group,
+                if_value,
+                else_value: Some(self.conditional_text('}')?),
+            }
+        } else {
+            self.accept('-');
+            FormatItem::Conditional {
+                group,
+                if_value: None,
+                else_value: Some(self.conditional_text('}')?),
+            }
+        };
+        self.accept('}').then_some(item)
+    }
+
+    /// Reads the text of a conditional format item, up to (not consuming) the `end`
+    fn conditional_text(&mut self, end: char) -> Option<String> {
+        let start = self.index;
+        if self.unclosed.get(&end).is_some_and(|from| *from <= start) {
+            return None;
+        }
+        let mut text = String::new();
+        loop {
+            match self.peek(0) {
+                None => {
+                    self.unclosed.insert(end, start);
+                    return None;
+                }
+                Some('\\') => {
+                    text.extend(self.peek(1));
+                    self.index += 2;
+                }
+                Some(c) if c == end => return Some(text),
+                Some(c) => {
+                    text.push(c);
+                    self.index += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Helper to give a placeholder or variable the default read for it
+fn with_default(marker: Marker, markers: Vec<Marker>) -> Marker {
+    match marker {
+        Marker::Placeholder { index, .. } => Marker::Placeholder {
+            index,
+            children: markers,
+        },
+        Marker::Variable {
+            name, transform, ..
+        } => Marker::Variable {
+            name,
+            default: Some(markers),
+            transform,
+        },
+        marker => marker,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tabstop(index: usize) -> Marker {
+        Marker::Tabstop {
+            index,
+            transform: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_tabstops_and_text() {
+        let ast = parse_body(&[
+            "fn $1(${2}) {".to_string(),
+            "\t$0".to_string(),
+            "}".to_string(),
+        ]);
+
+        assert_eq!(
+            ast.markers,
+            vec![
+                Marker::Text("fn ".to_string()),
+                tabstop(1),
+                Marker::Text("(".to_string()),
+                tabstop(2),
+                Marker::Text(") {\n\t".to_string()),
+                tabstop(0),
+                Marker::Text("\n}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_placeholders() {
+        let ast = parse_snippet("${1:foo ${2:bar}}");
+
+        assert_eq!(
+            ast.markers,
+            vec![Marker::Placeholder {
+                index: 1,
+                children: vec![
+                    Marker::Text("foo ".to_string()),
+                    Marker::Placeholder {
+                        index: 2,
+                        children: vec![Marker::Text("bar".to_string())]
+                    }
+                ]
+            }]
+        );
+        assert_eq!(ast.tabstops(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_parse_choices_and_variables() {
+        let ast = parse_snippet("${1|a,b\\,c|} $TM_FILENAME ${CURRENT_YEAR} ${NAME:default}");
+
+        assert_eq!(
+            ast.markers,
+            vec![
+                Marker::Choice {
+                    index: 1,
+                    options: vec!["a".to_string(), "b,c".to_string()]
+                },
+                Marker::Text(" ".to_string()),
+                Marker::Variable {
+                    name: "TM_FILENAME".to_string(),
+                    default: None,
+                    transform: None
+                },
+                Marker::Text(" ".to_string()),
+                Marker::Variable {
+                    name: "CURRENT_YEAR".to_string(),
+                    default: None,
+                    transform: None
+                },
+                Marker::Text(" ".to_string()),
+                Marker::Variable {
+                    name: "NAME".to_string(),
+                    default: Some(vec![Marker::Text("default".to_string())]),
+                    transform: None
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_transforms() {
+        let ast = parse_snippet("${1/(.*)\\/x/${1:/upcase}-$2${3:?yes:no}${4:+if}${5:-else}/gi}");
+
+        assert_eq!(
+            ast.markers,
+            vec![Marker::Tabstop {
+                index: 1,
+                transform: Some(Transform {
+                    regex: "(.*)/x".to_string(),
+                    format: vec![
+                        FormatItem::Modifier {
+                            group: 1,
+                            modifier: "upcase".to_string()
+                        },
+                        FormatItem::Text("-".to_string()),
+                        FormatItem::Group(2),
+                        FormatItem::Conditional {
+                            group: 3,
+                            if_value: Some("yes".to_string()),
+                            else_value: Some("no".to_string())
+                        },
+                        FormatItem::Conditional {
+                            group: 4,
+                            if_value: Some("if".to_string()),
+                            else_value: None
+                        },
+                        FormatItem::Conditional {
+                            group: 5,
+                            if_value: None,
+                            else_value: Some("else".to_string())
+                        },
+                    ],
+                    options: "gi".to_string()
+                })
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_syntax_as_text() {
+        let ast = parse_snippet("cost: $ ${} ${1:open \\$5 }");
+
+        assert_eq!(
+            ast.markers,
+            vec![
+                Marker::Text("cost: $ ${} ".to_string()),
+                Marker::Placeholder {
+                    index: 1,
+                    children: vec![Marker::Text("open $5 ".to_string())]
+                },
+            ]
+        );
+        assert_eq!(
+            parse_snippet("${1:never closed").markers,
+            vec![Marker::Text("${1:never closed".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_unclosed_placeholders_in_linear_time() {
+        let start = std::time::Instant::now();
+        for opening in [
+            "${1:",
+            "${NAME:",
+            "${1:a ${2|b,",
+            "${1/(.*)/${1:?",
+            "${1/x/${1:+",
+        ] {
+            let body = opening.repeat(20_000);
+            let ast = parse_snippet(&body);
+            assert_eq!(ast.markers, vec![Marker::Text(body.clone())], "{}", opening);
+        }
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let bodies = [
+            "fn ${1:name}(${2}) -> $3 {\n\t${1:name}$0\n}",
+            "${2:foo ${3:bar}} ${1|a,b\\,c|} $TM_FILENAME ${UNKNOWN:x} \\$5 ${1/(.*)/$1/}",
+            "${TM_FILENAME/(.*)\\.(.*)/${1:/pascalcase}${2:?.:none}/g} C:\\path \\\\$1",
+        ];
+
+        for body in bodies {
+            let ast = parse_snippet(body);
+            assert_eq!(parse_snippet(&ast.display()), ast, "{}", body);
+        }
+    }
+}
@@ -1,29 +1,36 @@
 //! Functions related to creating and manipulating FriendlySnippets (JSON)
 
+use super::jsonc_tekton::{parse_jsonc, strip_jsonc};
 use super::snipmate_tekton::build_snippets_from_file;
 use crate::{
+    core::editor::edit_snippet,
     errors::TektonError,
     models::{
-        friendly::{FriendlySnippetBody, FriendlySnippets, Table},
+        friendly::{take_extra, FriendlySnippetBody, FriendlySnippets, Table},
+        naming::{unique_name, SnippetNaming},
+        repair::PrefixRepair,
         snipmate::Snipmate,
+        sort::SortOrder,
     },
-    utils::{clear_terminal, get_input, hash2ordered_string},
+    utils::{clear_terminal, get_input, hash2ordered_string, read_input},
 };
 use regex::Regex;
-use std::{collections::HashMap, fs};
-
-const MISSING_PREFIX: &str = "File contains snippets with missing prefix field(s). Aborting.";
+use std::collections::{BTreeMap, HashMap};
 
 /// Function to handle Snipmate to JSON
 ///
 /// Arguments:
 /// - `lines`: the lines of Snipmate snippets to be converted
+/// - `naming`: how to name the snippets
 ///
 /// Returns:
 /// - The converted JSON string representation or an error
-pub fn compose_friendly_snippets(lines: Vec<String>) -> Result<String, TektonError> {
-    let snips = build_snippets_from_file(lines);
-    let friendlies = convert_snipmate_to_friendlysnippets(snips);
+pub fn compose_friendly_snippets(
+    lines: Vec<String>,
+    naming: &SnippetNaming,
+) -> Result<String, TektonError> {
+    let snips = build_snippets_from_file(lines)?;
+    let friendlies = convert_snipmate_to_friendlysnippets(snips, naming);
     let result = build_friendly_string(friendlies)?;
     Ok(result)
 }
@@ -36,39 +43,56 @@ pub fn compose_friendly_snippets(lines: Vec<String>) -> Result<String, TektonErr
 /// Returns:
 /// - The string with 1 to many snippets or an error.
 pub fn build_friendly_string(friendlies: FriendlySnippets) -> Result<String, TektonError> {
-    hash2ordered_string(&friendlies.snippets)
+    hash2ordered_string(&friendlies.snippets, &SortOrder::default())
 }
 
 /// A function to convert an array of Snipmate structs to an array of FriendlySnippet structs
 ///
+/// The snippets are named following `naming`, and only prompted for when it is interactive. A name
+/// that is already taken gets a number added (`for (2)`), with a warning.
+///
 /// Arguments:
 /// - `snips`: the vector of Snipmate snippets to be converted
+/// - `naming`: how to name the snippets
 ///
 /// Returns:
 /// - The table of FriendlySnippets with 0 to many snippets (more or less).
-pub fn convert_snipmate_to_friendlysnippets(snips: Vec<Snipmate>) -> FriendlySnippets {
+pub fn convert_snipmate_to_friendlysnippets(
+    snips: Vec<Snipmate>,
+    naming: &SnippetNaming,
+) -> FriendlySnippets {
     let mut count: usize = 0;
     let target = snips.len();
     let mut friendly_handle: FriendlySnippets = FriendlySnippets::new();
 
     for snippet in snips {
         let friendly_body = friendly_tekton(snippet);
+        let prefix = friendly_body.prefix.clone().unwrap_or_default();
+        count += 1;
 
-        match serde_json::to_string_pretty(&friendly_body) {
-            Ok(snip) => {
-                count += 1;
+        let key = match naming.name(&prefix, friendly_body.description.as_deref()) {
+            Some(key) => key,
+            None => {
                 clear_terminal();
                 println!(
                     "Snippet {} of {}:\n{}\n\nEnter name below:",
-                    count, target, snip
+                    count,
+                    target,
+                    serde_json::to_string_pretty(&friendly_body).unwrap_or_default()
                 );
-                let key = get_input();
-                friendly_handle.snippets.insert(key, friendly_body);
-            }
-            Err(e) => {
-                eprintln!("Match had an error in conversion ----> {}", e);
+                get_input()
             }
+        };
+        let unique = unique_name(key.clone(), |name| {
+            friendly_handle.snippets.contains_key(name)
+        });
+        if unique != key {
+            eprintln!(
+                "[ WARN ]: The name `{}` is already taken, the snippet `{}` is named `{}`",
+                key, prefix, unique
+            );
         }
+        friendly_handle.snippets.insert(unique, friendly_body);
     }
     friendly_handle
 }
@@ -96,19 +120,53 @@ fn friendly_tekton(snippet: Snipmate) -> FriendlySnippetBody {
 
 /// Helper function to read the JSON as a `FriendlySnippets` struct
 ///
+/// Comments and trailing commas (JSONC, as in VS Code snippet files) are allowed.
+///
 /// Arguments:
 /// - `file_name`: a string of the input file
-/// - `interactive`: a boolean indicating if the user will be invovled or not
+/// - `repair`: how to repair the snippets missing a prefix, from the answers or by prompting the user
 pub fn read_in_json_snippets(
     file_name: &str,
-    interactive: bool,
+    repair: &PrefixRepair,
 ) -> Result<FriendlySnippets, TektonError> {
-    let file_contents = fs::read_to_string(file_name)?;
+    let file_contents = strip_jsonc(&read_input(file_name)?);
     let snippets: Result<FriendlySnippets, serde_json::Error> =
         serde_json::from_str(&file_contents);
     match snippets {
-        Ok(snippets) => Ok(snippets),
-        Err(_) => match dynamically_read_json_snippets(file_contents, interactive) {
+        Ok(mut snippets) => {
+            let mut missing: Vec<String> = snippets
+                .snippets
+                .iter()
+                .filter(|(_, snippet)| snippet.prefix.is_none())
+                .map(|(name, _)| name.to_string())
+                .collect();
+            missing.sort();
+            // The snippets the user fixes, which are taken out of the table until they are
+            let mut snippets_to_fix: Vec<(String, FriendlySnippetBody)> = Vec::new();
+            for name in missing {
+                match repair.answer(&name) {
+                    Some(prefix) => {
+                        if let Some(snippet) = snippets.snippets.get_mut(&name) {
+                            snippet.prefix = Some(prefix.to_string());
+                        }
+                    }
+                    None if repair.interactive => {
+                        if let Some(snippet) = snippets.snippets.remove(&name) {
+                            snippets_to_fix.push((name, snippet));
+                        }
+                    }
+                    None => {
+                        return Err(TektonError::MissingPrefix {
+                            path: None,
+                            snippet: name,
+                        })
+                    }
+                }
+            }
+            correct_missing_prefix_snippets(&mut snippets_to_fix, &mut snippets.snippets, repair)?;
+            Ok(snippets)
+        }
+        Err(_) => match dynamically_read_json_snippets(file_contents, repair) {
             Ok(snippets) => Ok(snippets),
             Err(e) => Err(e),
         },
@@ -123,69 +181,110 @@ pub fn read_in_json_snippets(
 ///
 /// Arguments:
 /// - `file`: a string of the input file
-/// - `interactive`: a boolean indicating if the user will be invovled or not
+/// - `repair`: how to repair the snippets missing a prefix, from the answers or by prompting the user
 ///
 /// Returns:
 /// - Result of the snippets read in or an error
 pub fn dynamically_read_json_snippets(
     file: String,
-    interactive: bool,
+    repair: &PrefixRepair,
 ) -> Result<FriendlySnippets, TektonError> {
     // The snippet table (what is being created/ read in)
     let mut snippets: Table = HashMap::new();
     // The blob of JSON from serde_json
-    let json: serde_json::Value = serde_json::from_str(&file).unwrap();
+    let json: serde_json::Value = parse_jsonc(&file)?;
     // The 'need to fix this' pile
     let mut snippets_to_fix: Vec<(String, FriendlySnippetBody)> = Vec::new();
 
     if let Some(obj) = json.as_object() {
         for (name, v) in obj {
             // Collect the lines of the snippet body (outsourced to a helper function)
-            let body = retrieve_body(&v["body"]);
+            let body = retrieve_body(name, &v["body"])?;
 
             // Create snippet body assuming no description
             let mut snip_body = FriendlySnippetBody::new(None, body, None);
 
-            // If we find one, then update the structure
-            if let Some(description) = v["description"].as_str() {
-                if !description.is_empty() {
-                    snip_body.description = Some(description.to_string());
-                }
-            }
+            // The fields with an unexpected type (or an empty description) stay in the extra fields,
+            // so sorting doesn't lose them
+            let mut extra = retrieve_extra(v, &["prefix", "body"]);
+            snip_body.description = take_extra(&mut extra, "description", |v| {
+                v.as_str().filter(|d| !d.is_empty()).map(String::from)
+            });
+            snip_body.scope = take_extra(&mut extra, "scope", |v| v.as_str().map(String::from));
+            snip_body.is_file_template = take_extra(&mut extra, "isFileTemplate", |v| v.as_bool());
+            snip_body.include = take_extra(&mut extra, "include", |v| {
+                serde_json::from_value(v.clone()).ok()
+            });
+            snip_body.exclude = take_extra(&mut extra, "exclude", |v| {
+                serde_json::from_value(v.clone()).ok()
+            });
+            snip_body.extra = extra;
 
             // Find the prefix or add to the 'fix later' vec
             if let Some(pref_candidate) = retrieve_prefix(&v["prefix"]) {
                 snip_body.prefix = Some(pref_candidate);
-            } else if interactive {
+            } else if let Some(answer) = repair.answer(name) {
+                snip_body.prefix = Some(answer.to_string());
+            } else if repair.interactive {
                 // skip inserting a malformed snippet into the table, will fix later
                 snippets_to_fix.push((name.to_string(), snip_body));
                 continue;
             } else {
                 // Return an error, this is useful for automation since errors can be collected
                 // and addressed after the batch is finished.
-                return Err(TektonError::Reason(MISSING_PREFIX.into()));
+                return Err(TektonError::MissingPrefix {
+                    path: None,
+                    snippet: name.to_string(),
+                });
             }
 
             // Insert the finished snippet into the table
             snippets.insert(name.to_string(), snip_body);
         }
         // Congrats, it is later, now to fix the snippets
-        correct_missing_prefix_snippets(&mut snippets_to_fix, &mut snippets);
+        correct_missing_prefix_snippets(&mut snippets_to_fix, &mut snippets, repair)?;
     }
 
     Ok(FriendlySnippets { snippets })
 }
 
+/// Function to list the snippets of a JSON file that have no prefix at all (neither a string nor a list)
+///
+/// Arguments:
+/// - `contents`: the contents of the file
+///
+/// Returns:
+/// - The names of the snippets missing a prefix, or an error if the file isn't valid JSON
+pub fn missing_prefixes(contents: &str) -> Result<Vec<String>, TektonError> {
+    let json = parse_jsonc(contents)?;
+    Ok(json
+        .as_object()
+        .map(|table| {
+            table
+                .iter()
+                .filter(|(_, v)| !(v["prefix"].is_string() || v["prefix"].is_array()))
+                .map(|(name, _)| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
 /// A function to handle the correction of snippets that are missing their prefix
 ///
+/// The snippets are opened in `$EDITOR` with `--editor`, or else their prefix is prompted for.
+///
 /// Arguments:
 /// - `snippets_to_fix`: A mutable reference to a vector with the name and partial snippet body
 /// - `snippets`: A mutable reference to the table that the corrected snippet will be inserted into
+/// - `repair`: how to fix the snippets
 ///
+/// Returns:
+/// - An ok result, or an error if a snippet couldn't be fixed in the editor
 pub fn correct_missing_prefix_snippets(
     snippets_to_fix: &mut Vec<(String, FriendlySnippetBody)>,
     snippets: &mut Table,
-) {
+    repair: &PrefixRepair,
+) -> Result<(), TektonError> {
     if !snippets_to_fix.is_empty() {
         let mut count = 0;
         let total = snippets_to_fix.len();
@@ -197,11 +296,24 @@ pub fn correct_missing_prefix_snippets(
             if let Some((name, snip_body)) = snippets_to_fix.pop() {
                 count += 1;
                 println!("Fixing snippet {} of {}", count, total);
-                let snip_body = handle_prompt_for_prefix(&name, snip_body);
-                snippets.insert(name.to_string(), snip_body);
+                let (name, snip_body) = match repair.editor {
+                    Some(format) => edit_snippet(
+                        repair.editor_command.as_deref(),
+                        &name,
+                        snip_body,
+                        format,
+                        |other| {
+                            snippets.contains_key(other)
+                                || snippets_to_fix.iter().any(|(pending, _)| pending == other)
+                        },
+                    )?,
+                    None => (name.clone(), handle_prompt_for_prefix(&name, snip_body)),
+                };
+                snippets.insert(name, snip_body);
             }
         }
     }
+    Ok(())
 }
 
 /// A function that gets the users new prefix and updates the snippet, returning the properly formed body.
@@ -216,7 +328,7 @@ fn handle_prompt_for_prefix(name: &str, mut snip_body: FriendlySnippetBody) -> F
     println!(
         "---- Snippet: {} ---\n{}\n--------",
         name,
-        serde_json::to_string_pretty(&snip_body).ok().unwrap() // This unwrap will probably steal our lunch money later on.
+        serde_json::to_string_pretty(&snip_body).unwrap_or_default()
     );
     println!("Enter a prefix:");
     loop {
@@ -251,45 +363,189 @@ fn retrieve_prefix(val: &serde_json::Value) -> Option<String> {
 /// Function to handle processing the body of a JSON snippet
 ///
 /// Arguments:
+/// - `name`: the name of the snippet, for the error
 /// - `val`:  a reference to a serde_json::Value
 ///
 /// Returns:
-/// - `Vec<String>` representing the 'content' of the snippet
-pub fn retrieve_body(val: &serde_json::Value) -> Vec<String> {
-    let mut body: Vec<String> = Vec::new();
-    if let Some(lines) = val.as_array() {
-        for line in lines.iter() {
-            body.push(line.as_str().unwrap_or("").to_string());
-        }
-    } else {
-        body.push(val.as_str().unwrap_or("").to_string());
+/// - `Vec<String>` representing the 'content' of the snippet, or an error if a line isn't a string
+pub fn retrieve_body(name: &str, val: &serde_json::Value) -> Result<Vec<String>, TektonError> {
+    let not_text = || TektonError::Syntax {
+        path: None,
+        line: None,
+        message: format!("Snippet `{}` has a body that isn't text", name),
+    };
+    match val {
+        serde_json::Value::Array(lines) => lines
+            .iter()
+            .map(|line| line.as_str().map(String::from).ok_or_else(not_text))
+            .collect(),
+        serde_json::Value::String(line) => Ok(vec![line.to_string()]),
+        // A snippet without a body is read as an empty one
+        serde_json::Value::Null => Ok(vec![String::new()]),
+        _ => Err(not_text()),
     }
+}
 
-    body
+/// Function to collect the fields of a JSON snippet that aren't otherwise modeled
+///
+/// Arguments:
+/// - `val`: a reference to a serde_json::Value that represents the snippet
+/// - `known`: the names of the fields that are read in elsewhere
+///
+/// Returns:
+/// - The remaining fields, as-is
+pub fn retrieve_extra(
+    val: &serde_json::Value,
+    known: &[&str],
+) -> serde_json::Map<String, serde_json::Value> {
+    match val.as_object() {
+        Some(obj) => obj
+            .iter()
+            .filter(|(key, _)| !known.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        None => serde_json::Map::new(),
+    }
 }
 
 /// Function that builds a string representing the snippets in sorted order
 ///
 /// Arguments:
 /// - `snippets`: The snippets to be sorted
+/// - `order`: The order to write the snippets in
 ///
 /// Returns:
 /// - A result with the sorted string or an error if there were zero (0) snippets
-pub fn sort_friendly_snippets(snippets: FriendlySnippets) -> Result<String, TektonError> {
+pub fn sort_friendly_snippets(
+    snippets: FriendlySnippets,
+    order: &SortOrder,
+) -> Result<String, TektonError> {
     let table = &snippets.snippets;
     match table.len() {
-        0 => Err(TektonError::Reason(
-            "Refusing to build string for 0 snippets".to_string(),
-        )),
-        _ => hash2ordered_string(table),
+        0 => Err(TektonError::EmptyCollection { path: None }),
+        _ => hash2ordered_string(table, order),
+    }
+}
+
+/// Function to put the fields of the sorted snippets back in the order they had in the original file
+///
+/// The snippets are written with their fields in the order of the model, and with a list for the
+/// prefixes of the multi-prefix snippets and for the body, so this puts back the order and the single
+/// string `prefix` and `body` of the original to keep an already sorted file as it is. The fields added
+/// while reading (e.g. a repaired prefix) come after the original ones.
+///
+/// Arguments:
+/// - `sorted`: the pretty printed JSON of the sorted snippets
+/// - `original`: the contents of the file that was sorted
+///
+/// Returns:
+/// - The sorted JSON string, or an error if either of them isn't valid JSON
+pub fn restore_field_order(sorted: &str, original: &str) -> Result<String, TektonError> {
+    let original = parse_jsonc(original)?;
+    let mut table: serde_json::Map<String, serde_json::Value> = serde_json::from_str(sorted)?;
+    for (name, snippet) in table.iter_mut() {
+        let (Some(fields), Some(before)) = (snippet.as_object(), original[name].as_object()) else {
+            continue;
+        };
+        let mut ordered = serde_json::Map::new();
+        for (key, old) in before {
+            if let Some(value) = fields.get(key) {
+                let single = ["prefix", "body"].contains(&key.as_str())
+                    && *value == serde_json::json!([old]);
+                let value = if single { old } else { value };
+                ordered.insert(key.clone(), value.clone());
+            }
+        }
+        for (key, value) in fields {
+            if !ordered.contains_key(key) {
+                ordered.insert(key.clone(), value.clone());
+            }
+        }
+        *snippet = serde_json::Value::Object(ordered);
     }
+    Ok(serde_json::to_string_pretty(&table)?)
+}
+
+/// Function to split the snippets into one table per language of their `scope`
+///
+/// A snippet with several languages (`"javascript,typescript"`) is copied into each of their tables.
+///
+/// Arguments:
+/// - `friendlies`: the snippets to split
+///
+/// Returns:
+/// - The tables keyed by language, with `None` holding the snippets without a scope
+pub fn split_by_scope(friendlies: FriendlySnippets) -> BTreeMap<Option<String>, FriendlySnippets> {
+    let mut tables: BTreeMap<Option<String>, FriendlySnippets> = BTreeMap::new();
+    for (name, snippet) in friendlies.snippets {
+        let languages: Vec<Option<String>> = match &snippet.scope {
+            Some(scope) => scope
+                .split(',')
+                .map(|language| language.trim())
+                .filter(|language| !language.is_empty())
+                .map(|language| Some(language.to_string()))
+                .collect(),
+            None => vec![None],
+        };
+        let languages = match languages.is_empty() {
+            true => vec![None],
+            false => languages,
+        };
+        for language in languages {
+            tables
+                .entry(language)
+                .or_default()
+                .snippets
+                .insert(name.clone(), snippet.clone());
+        }
+    }
+    tables
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants, clippy::to_string_in_format_args)]
 mod tests {
 
     use super::*;
-    const INTERACTIVE: bool = false;
+    use crate::models::repair::EditFormat;
+
+    #[test]
+    fn test_snipmate_names_without_prompts() -> Result<(), TektonError> {
+        let lines: Vec<String> = [
+            "snippet for A for loop",
+            "\tfor ${1:i} in $2 {}",
+            "snippet for \"A second loop\"",
+            "\tfor $1 {}",
+            "snippet fn",
+            "\tfn $1() {}",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+        let naming = SnippetNaming {
+            template: Some("{prefix} - {description}".to_string()),
+            mapping: HashMap::from([("fn".to_string(), "Function".to_string())]),
+            ..SnippetNaming::default()
+        };
+
+        let friendlies =
+            convert_snipmate_to_friendlysnippets(build_snippets_from_file(lines.clone())?, &naming);
+        let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["Function", "for - A for loop", "for - A second loop"]
+        );
+
+        let friendlies = convert_snipmate_to_friendlysnippets(
+            build_snippets_from_file(lines)?,
+            &SnippetNaming::default(),
+        );
+        let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["fn", "for", "for (2)"]);
+        Ok(())
+    }
 
     #[test]
     fn standard_json_reading() {
@@ -344,7 +600,7 @@ mod tests {
         }"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(res) => {
@@ -375,7 +631,7 @@ mod tests {
         }}"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(res) => {
@@ -406,7 +662,7 @@ mod tests {
     }}"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(res) => {
@@ -443,7 +699,7 @@ mod tests {
     }}"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(res) => {
@@ -478,18 +734,108 @@ mod tests {
     }}"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(_) => {
                 assert!(false);
             }
             Err(e) => {
-                assert_eq!(e, TektonError::Reason(MISSING_PREFIX.into()));
+                assert!(
+                    matches!(e, TektonError::MissingPrefix { snippet, .. } if snippet == "Filter downcase")
+                );
             }
         }
     }
 
+    #[test]
+    fn missing_prefix_from_answers() -> Result<(), TektonError> {
+        let file = r#"{
+        "Filter downcase": { "body": "| downcase }}" },
+        "Filter upcase": { "prefix": "upcase", "body": ["| upcase }}"] },
+        "Filter capitalize": { "prefix": ["cap", "capitalize"], "body": "| capitalize }}" }
+    }"#
+        .to_string();
+        let repair = PrefixRepair {
+            answers: HashMap::from([("Filter downcase".to_string(), "downcase".to_string())]),
+            ..PrefixRepair::default()
+        };
+
+        assert_eq!(missing_prefixes(&file)?, vec!["Filter downcase"]);
+        let file = file.replace("[\"cap\", \"capitalize\"]", "\"cap\"");
+        let res = dynamically_read_json_snippets(file, &repair)?;
+        assert_eq!(
+            res.snippets["Filter downcase"].prefix.as_deref(),
+            Some("downcase")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn missing_prefix_with_a_list_body() -> Result<(), TektonError> {
+        let fname = std::env::temp_dir()
+            .join(format!("tekton_missing_prefix_{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        std::fs::write(
+            &fname,
+            r#"{ "Filter downcase": { "body": ["| downcase }}"] } }"#,
+        )?;
+        let repair = PrefixRepair {
+            answers: HashMap::from([("Filter downcase".to_string(), "downcase".to_string())]),
+            ..PrefixRepair::default()
+        };
+
+        let unanswered = read_in_json_snippets(&fname, &PrefixRepair::default());
+        let answered = read_in_json_snippets(&fname, &repair);
+        std::fs::remove_file(&fname)?;
+
+        assert!(matches!(
+            unanswered,
+            Err(TektonError::MissingPrefix { snippet, .. }) if snippet == "Filter downcase"
+        ));
+        assert_eq!(
+            answered?.snippets["Filter downcase"].prefix.as_deref(),
+            Some("downcase")
+        );
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn missing_prefix_fixed_in_the_editor() -> Result<(), TektonError> {
+        let dir = std::env::temp_dir();
+        let fname = dir
+            .join(format!("tekton_editor_prefix_{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let script = dir.join(format!("tekton_editor_prefix_{}.sh", std::process::id()));
+        std::fs::write(
+            &fname,
+            r#"{ "Filter downcase": { "body": ["| downcase }}"] } }"#,
+        )?;
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nsed -i 's/\"prefix\": \"\"/\"prefix\": \"dc\"/' \"$1\"\n",
+        )?;
+        let repair = PrefixRepair {
+            interactive: true,
+            editor: Some(EditFormat::Json),
+            editor_command: Some(format!("sh {}", script.to_string_lossy())),
+            ..PrefixRepair::default()
+        };
+
+        let res = read_in_json_snippets(&fname, &repair);
+        std::fs::remove_file(&fname)?;
+        std::fs::remove_file(&script)?;
+
+        assert_eq!(
+            res?.snippets["Filter downcase"].prefix.as_deref(),
+            Some("dc")
+        );
+        Ok(())
+    }
+
     #[test]
     fn serialization_missing_prefix_throw_error() {
         let file = r#"{
@@ -499,18 +845,118 @@ mod tests {
     }"#
         .to_string();
 
-        let res = dynamically_read_json_snippets(file, INTERACTIVE);
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default());
 
         match res {
             Ok(_) => {
                 assert!(false, "Failed to throw the error");
             }
             Err(e) => {
-                assert_eq!(e, TektonError::Reason(MISSING_PREFIX.into()));
+                assert!(
+                    matches!(e, TektonError::MissingPrefix { snippet, .. } if snippet == "Filter downcase")
+                );
             }
         }
     }
 
+    #[test]
+    fn body_lines_must_be_text() {
+        let file = r#"{"a": {"prefix": "a", "body": ["x", 1]}}"#;
+
+        let err =
+            dynamically_read_json_snippets(file.to_string(), &PrefixRepair::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid snippets: Snippet `a` has a body that isn't text"
+        );
+    }
+
+    #[test]
+    fn sorted_file_round_trip_is_byte_identical() -> Result<(), TektonError> {
+        // Already sorted, with the fields in any order and known fields of an unexpected type
+        let file = r#"{
+  "Alpha": {
+    "body": [
+      "a"
+    ],
+    "prefix": "a",
+    "scope": [
+      "rust"
+    ],
+    "description": "A",
+    "meta": {
+      "n": 1
+    }
+  },
+  "beta": {
+    "tags": [
+      "x",
+      "y"
+    ],
+    "prefix": "b",
+    "body": [
+      "b"
+    ],
+    "description": "",
+    "author": "me"
+  },
+  "gamma": {
+    "prefix": "g",
+    "body": [
+      "g"
+    ],
+    "include": "*.rs",
+    "isFileTemplate": "true"
+  }
+}"#;
+        let sort = |snippets: FriendlySnippets| -> Result<String, TektonError> {
+            restore_field_order(
+                &sort_friendly_snippets(snippets, &SortOrder::default())?,
+                file,
+            )
+        };
+
+        let dynamic = dynamically_read_json_snippets(file.to_string(), &PrefixRepair::default())?;
+        assert_eq!(sort(dynamic)?, file);
+
+        // The typed reader only reads the files whose fields have the expected types
+        let file = file
+            .replace("\"true\"", "true")
+            .replace("[\n      \"rust\"\n    ]", "\"rust\"");
+        let typed: FriendlySnippets = serde_json::from_str(&file)?;
+        assert_eq!(
+            restore_field_order(
+                &sort_friendly_snippets(typed, &SortOrder::default())?,
+                &file
+            )?,
+            file
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn split_code_snippets_by_scope() {
+        let file = r#"{
+            "log": { "scope": "javascript, typescript", "prefix": "log", "body": "console.log($1)" },
+            "fn": { "scope": "rust", "prefix": "fn", "body": "fn $1() {}" },
+            "todo": { "prefix": "todo", "body": "TODO: $1" }
+        }"#
+        .to_string();
+
+        let res = dynamically_read_json_snippets(file, &PrefixRepair::default()).unwrap();
+        let tables = split_by_scope(res);
+
+        let languages: Vec<Option<&str>> = tables.keys().map(|k| k.as_deref()).collect();
+        assert_eq!(
+            languages,
+            vec![None, Some("javascript"), Some("rust"), Some("typescript")]
+        );
+        assert!(tables[&None].snippets.contains_key("todo"));
+        assert!(tables[&Some("typescript".to_string())]
+            .snippets
+            .contains_key("log"));
+    }
+
     #[test]
     fn test_friendly_tekton() {
         let input: Vec<String> = vec![
@@ -522,7 +968,7 @@ mod tests {
             "    lines.".to_string(),
         ];
 
-        let snippets = build_snippets_from_file(input);
+        let snippets = build_snippets_from_file(input).unwrap();
 
         assert_eq!(snippets.len(), 2);
         let vsnip = snippets[0].clone();
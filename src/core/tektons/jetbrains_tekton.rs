@@ -0,0 +1,227 @@
+//! Functions related to exporting JetBrains Live Templates (`templateSet` XML)
+
+use crate::{
+    core::parser::parse_snippet,
+    errors::TektonError,
+    models::{
+        ast::Marker,
+        friendly::FriendlySnippets,
+        jetbrains::{LiveTemplate, TemplateVariable},
+    },
+    utils::xml_escape,
+};
+
+/// Snippet variables with a matching JetBrains expression
+const VARIABLE_EXPRESSIONS: [(&str, &str); 14] = [
+    ("TM_FILENAME", "fileName()"),
+    ("TM_FILENAME_BASE", "fileNameWithoutExtension()"),
+    ("TM_LINE_NUMBER", "lineNumber()"),
+    ("CLIPBOARD", "clipboard()"),
+    ("CURRENT_YEAR", "date(\"yyyy\")"),
+    ("CURRENT_YEAR_SHORT", "date(\"yy\")"),
+    ("CURRENT_MONTH", "date(\"MM\")"),
+    ("CURRENT_MONTH_NAME", "date(\"MMMM\")"),
+    ("CURRENT_MONTH_NAME_SHORT", "date(\"MMM\")"),
+    ("CURRENT_DATE", "date(\"dd\")"),
+    ("CURRENT_DAY_NAME", "date(\"EEEE\")"),
+    ("CURRENT_HOUR", "time(\"HH\")"),
+    ("CURRENT_MINUTE", "time(\"mm\")"),
+    ("CURRENT_SECOND", "time(\"ss\")"),
+];
+
+/// A function to convert JSON snippets to a JetBrains `templateSet`
+///
+/// Arguments:
+/// - `friendlies`: the structure holding the table of snippets.
+/// - `group`: the name of the template group (e.g. the language)
+///
+/// Returns:
+/// - Result of the composed XML string representation or an error
+pub fn compose_jetbrains_templates(
+    friendlies: FriendlySnippets,
+    group: &str,
+) -> Result<String, TektonError> {
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection { path: None });
+    }
+
+    let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+    names.sort_by_key(|name| name.to_lowercase());
+
+    let mut xml_string = format!("<templateSet group=\"{}\">\n", xml_escape(group));
+    for name in names {
+        let snippet = &friendlies.snippets[name];
+        let prefix = snippet
+            .prefix
+            .clone()
+            .ok_or_else(|| TektonError::MissingPrefix {
+                path: None,
+                snippet: name.to_string(),
+            })?;
+        let (value, variables) = translate_placeholders(&snippet.body.join("\n"));
+        let description = snippet
+            .description
+            .clone()
+            .filter(|description| !description.is_empty())
+            .or_else(|| Some(name.to_string()));
+
+        xml_string += &LiveTemplate::new(prefix, value, description, variables).display();
+    }
+    xml_string += "</templateSet>\n";
+
+    Ok(xml_string)
+}
+
+/// Function to translate a snippet body into the JetBrains template syntax
+///
+/// - `$1`, `${1}` and `${1:default}` become `$VAR1$` with a `defaultValue` of `"default"`
+/// - `${1|a,b|}` becomes `$VAR1$` with an `enum("a","b")` expression
+/// - `$0` becomes `$END$` and `$TM_SELECTED_TEXT` becomes `$SELECTION$`
+/// - Other variables (`$TM_FILENAME`, `${CURRENT_YEAR}`, ...) use the closest JetBrains expression
+/// - Nested placeholders are flattened into the default of the outer one, and
+///   transforms are dropped (only the tabstop is kept)
+///
+/// Arguments:
+/// - `body`: the snippet body as a single string
+///
+/// Returns:
+/// - The template text and the variables it uses, in the order the cursor visits them
+pub fn translate_placeholders(body: &str) -> (String, Vec<TemplateVariable>) {
+    let mut value = String::new();
+    let mut tabstops: Vec<(usize, TemplateVariable)> = Vec::new();
+    let mut variables: Vec<TemplateVariable> = Vec::new();
+
+    for marker in parse_snippet(body).markers {
+        let (number, default, expression) = match &marker {
+            Marker::Text(text) => {
+                value += &text.replace('$', "$$");
+                continue;
+            }
+            Marker::Tabstop { index, .. } => (*index, String::new(), String::new()),
+            Marker::Placeholder { index, .. } => (*index, marker.plain_text(), String::new()),
+            Marker::Choice { index, options } => {
+                let choices: Vec<String> = options.iter().map(|choice| quote(choice)).collect();
+                let expression = format!("enum({})", choices.join(","));
+                (*index, marker.plain_text(), expression)
+            }
+            Marker::Variable { name, .. } => {
+                if name == "TM_SELECTED_TEXT" || name == "SELECTION" {
+                    value += "$SELECTION$";
+                    continue;
+                }
+                value += &format!("${}$", name);
+                if !variables.iter().any(|variable| &variable.name == name) {
+                    let default = quote_non_empty(&marker.plain_text());
+                    let variable = match VARIABLE_EXPRESSIONS.iter().find(|(n, _)| n == name) {
+                        Some((_, expression)) => TemplateVariable::new(
+                            name.to_string(),
+                            expression.to_string(),
+                            default,
+                            false,
+                        ),
+                        None => {
+                            TemplateVariable::new(name.to_string(), String::new(), default, true)
+                        }
+                    };
+                    variables.push(variable);
+                }
+                continue;
+            }
+        };
+
+        if number == 0 {
+            value += "$END$";
+            continue;
+        }
+        let var_name = format!("VAR{}", number);
+        value += &format!("${}$", var_name);
+        match tabstops.iter_mut().find(|(n, _)| *n == number) {
+            Some((_, variable)) => {
+                if variable.default_value.is_empty() && !default.is_empty() {
+                    variable.default_value = quote(&default);
+                }
+                if variable.expression.is_empty() {
+                    variable.expression = expression;
+                }
+            }
+            None => tabstops.push((
+                number,
+                TemplateVariable::new(var_name, expression, quote_non_empty(&default), true),
+            )),
+        }
+    }
+
+    tabstops.sort_by_key(|(number, _)| *number);
+    let mut ordered: Vec<TemplateVariable> =
+        tabstops.into_iter().map(|(_, variable)| variable).collect();
+    ordered.extend(variables);
+    (value, ordered)
+}
+
+/// Helper to write text as a JetBrains string expression
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Helper to write text as a JetBrains string expression, leaving empty text empty
+fn quote_non_empty(text: &str) -> String {
+    match text.is_empty() {
+        true => String::new(),
+        false => quote(text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::friendly::FriendlySnippetBody;
+
+    #[test]
+    fn test_translate_tabstops_and_placeholders() {
+        let (value, variables) =
+            translate_placeholders("fn ${1:name}(${2}) -> $3 {\n\t${1:name}$0\n}");
+
+        assert_eq!(value, "fn $VAR1$($VAR2$) -> $VAR3$ {\n\t$VAR1$$END$\n}");
+        assert_eq!(
+            variables,
+            vec![
+                TemplateVariable::new("VAR1".into(), "".into(), "\"name\"".into(), true),
+                TemplateVariable::new("VAR2".into(), "".into(), "".into(), true),
+                TemplateVariable::new("VAR3".into(), "".into(), "".into(), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_translate_nested_choices_and_variables() {
+        let (value, variables) = translate_placeholders(
+            "${2:foo ${3:bar}} ${1|a,b\\,c|} $TM_FILENAME ${UNKNOWN:x} $TM_SELECTED_TEXT \\$5 ${1/(.*)/$1/}",
+        );
+
+        assert_eq!(
+            value,
+            "$VAR2$ $VAR1$ $TM_FILENAME$ $UNKNOWN$ $SELECTION$ $$5 $VAR1$"
+        );
+        assert_eq!(variables[0].expression, "enum(\"a\",\"b,c\")");
+        assert_eq!(variables[0].default_value, "\"a\"");
+        assert_eq!(variables[1].default_value, "\"foo bar\"");
+        assert_eq!(variables[2].expression, "fileName()");
+        assert!(!variables[2].always_stop_at);
+        assert_eq!(variables[3].default_value, "\"x\"");
+    }
+
+    #[test]
+    fn test_convert_json_to_jetbrains() -> Result<(), TektonError> {
+        let mut friendlies = FriendlySnippets::new();
+        friendlies.snippets.insert(
+            "print".into(),
+            FriendlySnippetBody::new(Some("pr".into()), vec!["print($1)".to_string()], None),
+        );
+
+        let xml = compose_jetbrains_templates(friendlies, "python")?;
+
+        assert!(xml.starts_with("<templateSet group=\"python\">\n  <template name=\"pr\" value=\"print($VAR1$)\" description=\"print\""));
+        assert!(xml.ends_with("</templateSet>\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+//! Functions related to reading JSON with comments (JSONC), as VS Code writes its snippet files
+//!
+//! VS Code accepts `//` and `/* */` comments and trailing commas in snippet files, which plain JSON
+//! parsers reject. These functions strip them before parsing, and keep the comments around so that
+//! sorting a file doesn't lose them.
+
+use crate::{errors::TektonError, models::jsonc::JsoncComments};
+
+/// Function to parse a JSONC string
+///
+/// Arguments:
+/// - `text`: the contents of the file
+///
+/// Returns:
+/// - The parsed JSON or an error explaining where the file is malformed
+pub fn parse_jsonc(text: &str) -> Result<serde_json::Value, TektonError> {
+    Ok(serde_json::from_str(&strip_jsonc(text))?)
+}
+
+/// Function to turn a JSONC string into plain JSON
+///
+/// Comments are replaced with whitespace (keeping the line breaks) and trailing commas are dropped,
+/// so the line and column numbers of any parse error still match the original file.
+///
+/// Arguments:
+/// - `text`: the contents of the file
+///
+/// Returns:
+/// - The JSON string
+pub fn strip_jsonc(text: &str) -> String {
+    scan_jsonc(text).0
+}
+
+/// Function to collect the comments of a JSONC snippet file
+///
+/// A comment belongs to the snippet it is inside of, the snippet it follows on the same line or
+/// otherwise the next snippet in the file.
+///
+/// Arguments:
+/// - `text`: the contents of the file
+///
+/// Returns:
+/// - The comments of the file
+pub fn collect_comments(text: &str) -> JsoncComments {
+    scan_jsonc(text).1
+}
+
+/// Function to put the comments of a JSONC file back into the sorted JSON of its snippets
+///
+/// The comments of a snippet are written on the lines before it, including any comments that were
+/// inside of the snippet (there is no way to know which field they should stay next to after sorting).
+///
+/// Arguments:
+/// - `sorted`: the pretty printed JSON of the sorted snippets
+/// - `comments`: the comments collected from the original file
+///
+/// Returns:
+/// - The sorted JSONC string, which is `sorted` as-is when there are no comments
+pub fn restore_comments(sorted: &str, comments: &JsoncComments) -> String {
+    if comments.is_empty() {
+        return sorted.to_string();
+    }
+
+    let entries: Vec<(String, &Vec<String>)> = comments
+        .entries
+        .iter()
+        .filter(|(_, entry)| !entry.is_empty())
+        .map(|(name, entry)| {
+            let key = serde_json::to_string(name).unwrap_or_default();
+            (format!("  {}: ", key), entry)
+        })
+        .collect();
+
+    let mut lines: Vec<String> = Vec::new();
+    push_comment_lines(&mut lines, &comments.header, "");
+    let sorted_lines: Vec<&str> = sorted.split('\n').collect();
+    for (index, line) in sorted_lines.iter().enumerate() {
+        if index == sorted_lines.len() - 1 {
+            push_comment_lines(&mut lines, &comments.footer, "  ");
+        }
+        if let Some((_, entry)) = entries.iter().find(|(key, _)| line.starts_with(key)) {
+            push_comment_lines(&mut lines, entry, "  ");
+        }
+        lines.push(line.to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// Helper to write comments as lines with the given indentation
+fn push_comment_lines(lines: &mut Vec<String>, comments: &[String], indent: &str) {
+    for comment in comments {
+        for line in comment.split('\n') {
+            lines.push(format!("{}{}", indent, line.trim()));
+        }
+    }
+}
+
+/// The single pass over a JSONC string that both strips it and collects its comments
+fn scan_jsonc(text: &str) -> (String, JsoncComments) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut json = String::with_capacity(text.len());
+    let mut comments = JsoncComments::default();
+
+    let mut index = 0;
+    let mut line = 1;
+    let mut depth: usize = 0;
+    let mut is_table = false;
+    let mut opened = false;
+    // Where (in `json`) and on which line the last character that wasn't whitespace is
+    let mut last_token: Option<usize> = None;
+    let mut last_token_line = 0;
+    let mut expecting_key = false;
+    let mut current_key: Option<String> = None;
+    let mut pending: Vec<String> = Vec::new();
+
+    while index < chars.len() {
+        let c = chars[index];
+        let next = chars.get(index + 1).copied();
+
+        if c == '/' && (next == Some('/') || next == Some('*')) {
+            let start_line = line;
+            let mut comment = String::new();
+            if next == Some('/') {
+                while index < chars.len() && chars[index] != '\n' {
+                    comment.push(chars[index]);
+                    json.push(' ');
+                    index += 1;
+                }
+            } else {
+                while index < chars.len() {
+                    let c = chars[index];
+                    comment.push(c);
+                    index += 1;
+                    if c == '\n' {
+                        line += 1;
+                        json.push('\n');
+                    } else {
+                        json.push(' ');
+                    }
+                    if c == '/' && comment.len() > 3 && comment.ends_with("*/") {
+                        break;
+                    }
+                }
+            }
+
+            let same_line = start_line == last_token_line;
+            if depth == 0 {
+                match opened {
+                    false => comments.header.push(comment),
+                    true => comments.footer.push(comment),
+                }
+            } else if !is_table {
+                pending.push(comment);
+            } else if depth > 1 || !expecting_key || same_line {
+                match &current_key {
+                    Some(key) => comments
+                        .entries
+                        .entry(key.clone())
+                        .or_default()
+                        .push(comment),
+                    None if same_line => comments.header.push(comment),
+                    None => pending.push(comment),
+                }
+            } else {
+                pending.push(comment);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                let mut raw = String::from('"');
+                index += 1;
+                while index < chars.len() {
+                    let c = chars[index];
+                    raw.push(c);
+                    index += 1;
+                    if c == '\\' && index < chars.len() {
+                        raw.push(chars[index]);
+                        index += 1;
+                    } else if c == '"' {
+                        break;
+                    } else if c == '\n' {
+                        line += 1;
+                    }
+                }
+                json.push_str(&raw);
+
+                if depth == 1 && is_table && expecting_key {
+                    let key: String = serde_json::from_str(&raw).unwrap_or(raw);
+                    let entry = comments.entries.entry(key.clone()).or_default();
+                    entry.append(&mut pending);
+                    current_key = Some(key);
+                    expecting_key = false;
+                }
+                last_token = Some(json.len() - 1);
+                last_token_line = line;
+                continue;
+            }
+            '{' | '[' => {
+                if depth == 0 && !opened {
+                    opened = true;
+                    is_table = c == '{';
+                    expecting_key = is_table;
+                }
+                depth += 1;
+            }
+            '}' | ']' => {
+                // A trailing comma is the last thing before the closing bracket
+                if let Some(comma) = last_token.filter(|i| json.as_bytes()[*i] == b',') {
+                    json.replace_range(comma..comma + 1, " ");
+                }
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    comments.footer.append(&mut pending);
+                    expecting_key = false;
+                }
+            }
+            ',' if depth == 1 => expecting_key = is_table,
+            '\n' => line += 1,
+            _ => {}
+        }
+
+        json.push(c);
+        if !c.is_whitespace() {
+            last_token = Some(json.len() - c.len_utf8());
+            last_token_line = line;
+        }
+        index += 1;
+    }
+    comments.footer.append(&mut pending);
+
+    (json, comments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"// Snippets for the rust files
+{
+  // Prints a line
+  "print": {
+    "prefix": "pr",
+    "body": ["println!(\"${1}\");"], // the body
+  },
+  "alpha": {
+    /* the
+       trigger */
+    "prefix": "al",
+    "body": ["// not a comment"],
+  }, // a trailing comment
+  // the end
+}"#;
+
+    #[test]
+    fn test_strip_jsonc() {
+        let json = strip_jsonc(FILE);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["alpha"]["body"][0], "// not a comment");
+        assert_eq!(json.lines().count(), FILE.lines().count());
+    }
+
+    #[test]
+    fn test_parse_jsonc_error() {
+        match parse_jsonc("{ \"a\": { \"body\": [1 2] } }") {
+            Ok(_) => panic!("Failed to throw the error"),
+            Err(e) => assert!(e.to_string().starts_with("Invalid JSON")),
+        }
+    }
+
+    #[test]
+    fn test_collect_comments() {
+        let comments = collect_comments(FILE);
+
+        assert_eq!(comments.header, vec!["// Snippets for the rust files"]);
+        assert_eq!(
+            comments.entries["print"],
+            vec!["// Prints a line", "// the body"]
+        );
+        assert_eq!(
+            comments.entries["alpha"],
+            vec!["/* the\n       trigger */", "// a trailing comment"]
+        );
+        assert_eq!(comments.footer, vec!["// the end"]);
+    }
+
+    #[test]
+    fn test_restore_comments() {
+        let sorted = "{\n  \"alpha\": {\n    \"prefix\": \"al\"\n  },\n  \"print\": {\n    \"prefix\": \"pr\"\n  }\n}";
+
+        assert_eq!(
+            restore_comments(sorted, &collect_comments(FILE)),
+            "// Snippets for the rust files\n{\n  /* the\n  trigger */\n  // a trailing comment\n  \"alpha\": {\n    \"prefix\": \"al\"\n  },\n  // Prints a line\n  // the body\n  \"print\": {\n    \"prefix\": \"pr\"\n  }\n  // the end\n}"
+        );
+        assert_eq!(
+            restore_comments(sorted, &collect_comments(sorted)),
+            sorted.to_string()
+        );
+    }
+}
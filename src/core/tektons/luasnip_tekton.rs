@@ -0,0 +1,138 @@
+//! Functions related to writing LuaSnip (Lua module) snippets
+
+use crate::{
+    errors::TektonError,
+    models::multiprefix_friendly::{MultiBody, MultiPrefixTable},
+};
+
+/// The header of every generated module, loading the LuaSnip parser
+const LUASNIP_HEADER: &str =
+    "local ls = require(\"luasnip\")\nlocal parse = ls.parser.parse_snippet\n\nreturn {\n";
+
+/// A function to convert JSON snippets to a LuaSnip module
+///
+/// Each prefix becomes its own `parse_snippet` call, so a snippet with several prefixes
+/// can be expanded by any of them. The bodies are kept in the LSP snippet syntax which
+/// `ls.parser.parse_snippet` understands.
+///
+/// Arguments:
+/// - `table`: the structure holding the table of snippets (see `MultiPrefixTable::from` for `FriendlySnippets`)
+///
+/// Returns:
+/// - Result of the composed Lua module or an error
+pub fn compose_luasnip_snippets(table: MultiPrefixTable) -> Result<String, TektonError> {
+    if table.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection { path: None });
+    }
+
+    let mut names: Vec<&String> = table.snippets.keys().collect();
+    names.sort_by_key(|name| name.to_lowercase());
+
+    let mut lua_string = String::from(LUASNIP_HEADER);
+    for name in names {
+        let snippet = &table.snippets[name];
+        if snippet.prefix.is_empty() {
+            return Err(TektonError::MissingPrefix {
+                path: None,
+                snippet: name.to_string(),
+            });
+        }
+        for prefix in snippet.prefix.iter() {
+            lua_string += &build_luasnip_string(name, prefix, snippet);
+        }
+    }
+    lua_string += "}\n";
+
+    Ok(lua_string)
+}
+
+/// Helper to build the `parse(...)` entry for one trigger of a snippet
+fn build_luasnip_string(name: &str, prefix: &str, snippet: &MultiBody) -> String {
+    let mut context: Vec<String> = vec![
+        format!("trig = {}", lua_quote(prefix)),
+        format!("name = {}", lua_quote(name)),
+    ];
+    if let Some(description) = &snippet.description {
+        if !description.is_empty() {
+            context.push(format!("dscr = {}", lua_quote(description)));
+        }
+    }
+    if let Some(word_trig) = snippet.word_trig {
+        context.push(format!("wordTrig = {}", word_trig));
+    }
+    if let Some(priority) = snippet.priority {
+        context.push(format!("priority = {}", priority));
+    }
+
+    format!(
+        "  parse({{ {} }}, {}),\n",
+        context.join(", "),
+        lua_quote(&snippet.body.join("\n"))
+    )
+}
+
+/// Helper to write a string as a double quoted Lua string literal
+fn lua_quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '\\' => quoted += "\\\\",
+            '"' => quoted += "\\\"",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            c if c.is_control() => quoted += &format!("\\{:03}", c as u32),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tektons::multiprefix_tekton::dynamic_prefix_combinator;
+
+    #[test]
+    fn test_lua_quote() {
+        assert_eq!(lua_quote("a \"b\"\n\t\\c"), "\"a \\\"b\\\"\\n\\t\\\\c\"");
+    }
+
+    #[test]
+    fn test_convert_json_to_luasnip() -> Result<(), TektonError> {
+        let file = r#"{
+            "print": {
+              "prefix": ["pr", "print"],
+              "body": ["print(\"${1}\")", "$0"],
+              "description": "print it",
+              "wordTrig": false,
+              "priority": 2000
+            },
+            "Alpha": {
+              "prefix": "a",
+              "body": "alpha"
+            }
+          }"#;
+
+        let table = dynamic_prefix_combinator(file)?;
+        let lua = compose_luasnip_snippets(table)?;
+
+        let expected = String::from(LUASNIP_HEADER)
+            + "  parse({ trig = \"a\", name = \"Alpha\" }, \"alpha\"),\n"
+            + "  parse({ trig = \"pr\", name = \"print\", dscr = \"print it\", wordTrig = false, priority = 2000 }, \"print(\\\"${1}\\\")\\n$0\"),\n"
+            + "  parse({ trig = \"print\", name = \"print\", dscr = \"print it\", wordTrig = false, priority = 2000 }, \"print(\\\"${1}\\\")\\n$0\"),\n"
+            + "}\n";
+        assert_eq!(lua, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_table() {
+        let table = MultiPrefixTable {
+            snippets: std::collections::HashMap::new(),
+        };
+
+        assert!(compose_luasnip_snippets(table).is_err());
+    }
+}
@@ -1,6 +1,12 @@
 pub mod friendly_tekton;
+pub mod jetbrains_tekton;
+pub mod jsonc_tekton;
+pub mod luasnip_tekton;
 pub mod multiprefix_tekton;
 pub mod snipmate_tekton;
+pub mod sublime_tekton;
+pub mod ultisnips_tekton;
+pub mod yasnippet_tekton;
 
 #[cfg(test)]
 mod tests {
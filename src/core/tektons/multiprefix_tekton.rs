@@ -1,35 +1,52 @@
 use crate::{
     errors::TektonError,
-    models::multiprefix_friendly::{MultiBody, MultiPrefixTable},
+    models::{
+        friendly::take_extra,
+        multiprefix_friendly::{MultiBody, MultiPrefixTable},
+    },
 };
 use std::collections::HashMap;
 
-use super::friendly_tekton::retrieve_body;
+use super::friendly_tekton::{retrieve_body, retrieve_extra};
+use super::jsonc_tekton::parse_jsonc;
 
 /// Essential the samething for the default
 pub fn dynamic_prefix_combinator(file_content: &str) -> Result<MultiPrefixTable, TektonError> {
     let mut snippets: HashMap<String, MultiBody> = HashMap::new();
-    let json: serde_json::Value = serde_json::from_str(file_content).unwrap();
+    let json: serde_json::Value = parse_jsonc(file_content)?;
 
     if let Some(obj) = json.as_object() {
         for (k, v) in obj {
             let name = k.clone();
-            let mut description: String = String::new();
-
             match retrieve_prefix(&v["prefix"]) {
-                Ok(prefix) => {
-                    let body = retrieve_body(&v["body"]);
-                    if let Some(val) = v["description"].as_str() {
-                        description.push_str(val);
-                    }
-                    let snip_body = MultiBody::new(prefix, body, description);
+                Some(prefix) => {
+                    let body = retrieve_body(&name, &v["body"])?;
+                    // The fields with an unexpected type stay in the extra fields, so they aren't lost
+                    let mut extra = retrieve_extra(v, &["prefix", "body"]);
+                    let mut snip_body = MultiBody::new(prefix, body, String::new());
+                    snip_body.description =
+                        take_extra(&mut extra, "description", |v| v.as_str().map(String::from));
+                    snip_body.scope =
+                        take_extra(&mut extra, "scope", |v| v.as_str().map(String::from));
+                    snip_body.is_file_template =
+                        take_extra(&mut extra, "isFileTemplate", |v| v.as_bool());
+                    snip_body.include = take_extra(&mut extra, "include", |v| {
+                        serde_json::from_value(v.clone()).ok()
+                    });
+                    snip_body.exclude = take_extra(&mut extra, "exclude", |v| {
+                        serde_json::from_value(v.clone()).ok()
+                    });
+                    snip_body.word_trig = take_extra(&mut extra, "wordTrig", |v| v.as_bool());
+                    snip_body.priority = take_extra(&mut extra, "priority", |v| v.as_i64());
+                    snip_body.extra = extra;
 
                     snippets.insert(name.to_string(), snip_body);
                 }
-                Err(e) => {
-                    let message =
-                        "Failed on snippet ---> ".to_string() + &name + " | " + &e.to_string();
-                    return Err(TektonError::Reason(message));
+                None => {
+                    return Err(TektonError::MissingPrefix {
+                        path: None,
+                        snippet: name,
+                    })
                 }
             }
         }
@@ -39,24 +56,31 @@ pub fn dynamic_prefix_combinator(file_content: &str) -> Result<MultiPrefixTable,
 }
 
 // Private function to handle parsing out the prefix.
-fn retrieve_prefix(val: &serde_json::Value) -> Result<Vec<String>, TektonError> {
+fn retrieve_prefix(val: &serde_json::Value) -> Option<Vec<String>> {
     if let Some(array) = val.as_array() {
-        Ok(array
-            .iter()
-            .filter_map(|e| e.as_str().map(|val| val.to_string()))
-            .collect())
-    } else if let Some(prefix) = val.as_str() {
-        Ok(vec![prefix.to_string()])
+        Some(
+            array
+                .iter()
+                .filter_map(|e| e.as_str().map(|val| val.to_string()))
+                .collect(),
+        )
     } else {
-        Err(TektonError::Reason(
-            "Check source file, not possible to construct snippet.".to_string(),
-        ))
+        val.as_str().map(|prefix| vec![prefix.to_string()])
     }
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::to_string_in_format_args,
+    clippy::useless_vec
+)]
 mod tests {
     use super::*;
+    use crate::{
+        core::tektons::friendly_tekton::restore_field_order, models::sort::SortOrder,
+        utils::hash2ordered_string,
+    };
 
     #[test]
     fn test_multiple_prefix_entries() {
@@ -104,6 +128,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sorted_file_round_trip_is_byte_identical() -> Result<(), TektonError> {
+        // Already sorted, with the fields in any order and known fields of an unexpected type
+        let file = r#"{
+  "alpha": {
+    "body": [
+      "a"
+    ],
+    "prefix": "a",
+    "tags": [
+      "x"
+    ],
+    "priority": "high"
+  },
+  "beta": {
+    "prefix": [
+      "b",
+      "bb"
+    ],
+    "author": "me",
+    "body": [
+      "b"
+    ],
+    "wordTrig": false
+  }
+}"#;
+
+        let sorted = hash2ordered_string(
+            &dynamic_prefix_combinator(file)?.snippets,
+            &SortOrder::default(),
+        )?;
+        assert_eq!(restore_field_order(&sorted, file)?, file);
+        Ok(())
+    }
+
     #[test]
     fn test_single_prefix_entries_in_array() {
         let file = r#"{
@@ -29,7 +29,7 @@ pub fn compose_snipmate_snippets(friendlies: FriendlySnippets) -> Result<String,
 /// - The built string or an error if there are zero (0) snippets
 pub fn build_snipmate_string(snippets: Vec<Snipmate>) -> Result<String, TektonError> {
     match snippets.len() {
-        0 => Err(TektonError::Reason("No snippets to convert".to_string())),
+        0 => Err(TektonError::EmptyCollection { path: None }),
         _ => {
             let mut snipmate_string = String::from("");
             for snip in snippets {
@@ -54,41 +54,37 @@ pub fn create_snipmate_structs_from_json(
 ) -> Result<Vec<Snipmate>, TektonError> {
     let table: Table = friendlies.snippets;
     if table.is_empty() {
-        return Err(TektonError::Reason("No snippets to convert".to_string()));
+        return Err(TektonError::EmptyCollection { path: None });
     }
 
-    let target = table.len();
-    let mut count: usize = 0;
     let mut snipmate_snippets: Vec<Snipmate> = Vec::new();
-    for (_name, v) in table {
-        if let Some(prefix) = v.prefix {
-            let snip: Snipmate = Snipmate {
-                prefix,
-                body: v.body,
-                description: v.description,
-            };
-            count += 1;
-            snipmate_snippets.push(snip)
-        }
+    for (name, v) in table {
+        let prefix = v.prefix.ok_or(TektonError::MissingPrefix {
+            path: None,
+            snippet: name,
+        })?;
+        let snip: Snipmate = Snipmate {
+            prefix,
+            body: v.body,
+            description: v.description,
+        };
+        snipmate_snippets.push(snip)
     }
 
-    println!("Count: {} || Target: {}", count, target);
-    match count == target {
-        true => Ok(snipmate_snippets),
-        false => Err(TektonError::Reason(
-            "Missing snippets in count, conversion aborted.".to_string(),
-        )),
-    }
+    Ok(snipmate_snippets)
 }
 
 /// Function to construct the Snipmate structs from a Vec<String> representing the snippet file that was read in.
 ///
+/// Blank lines and `#` comments before the first `snippet` header are skipped.
+///
 /// Arguments:
 /// - `lines`: a vector with the snipmate source file read in as a vec of strings
 ///
 /// Returns:
-/// - A vec of snipmate snippets (length can be 0), expects caller to check this condition
-pub fn build_snippets_from_file(lines: Vec<String>) -> Vec<Snipmate> {
+/// - A vec of snipmate snippets (length can be 0), expects caller to check this condition, or an error
+///   naming the first line that comes before any `snippet` header
+pub fn build_snippets_from_file(lines: Vec<String>) -> Result<Vec<Snipmate>, TektonError> {
     let mut snippets: Vec<Snipmate> = Vec::new();
     let tab = String::from("\\t");
     let tab_regex = Regex::new(&tab).unwrap();
@@ -98,7 +94,7 @@ pub fn build_snippets_from_file(lines: Vec<String>) -> Vec<Snipmate> {
         .build()
         .expect("failed");
     let re = Regex::new(r##"\\""##).unwrap();
-    for line in lines.iter() {
+    for (number, line) in lines.iter().enumerate() {
         // Construct a new snippet
         if set.is_match(line.as_bytes()) {
             let mut s = line.split_whitespace();
@@ -117,18 +113,28 @@ pub fn build_snippets_from_file(lines: Vec<String>) -> Vec<Snipmate> {
         }
         // Continue to add the body of the snippet to the most recently
         // added snippet struct.
-        else {
-            let index = snippets.len() - 1;
-            let handle = snippets.get_mut(index).unwrap();
+        else if let Some(handle) = snippets.last_mut() {
             handle
                 .body
                 .push(tab_regex.replace_all(&line.to_string(), "  ").to_string());
+        } else if !line.trim().is_empty() && !line.starts_with('#') {
+            return Err(TektonError::Syntax {
+                path: None,
+                line: Some(number + 1),
+                message: "Not part of any snippet, expected a `snippet` header before it"
+                    .to_string(),
+            });
         }
     }
-    snippets
+    Ok(snippets)
 }
 
 #[cfg(test)]
+#[allow(
+    clippy::assertions_on_constants,
+    clippy::get_first,
+    clippy::useless_vec
+)]
 mod tests {
     use crate::models::friendly::FriendlySnippetBody;
 
@@ -138,7 +144,7 @@ mod tests {
     fn test_building_snipmate_on_empty_string() {
         let input: Vec<String> = vec![];
 
-        let snippets = build_snippets_from_file(input);
+        let snippets = build_snippets_from_file(input).unwrap();
 
         assert_eq!(snippets.len(), 0);
     }
@@ -154,7 +160,7 @@ mod tests {
             "    lines.".to_string(),
         ];
 
-        let snippets = build_snippets_from_file(input);
+        let snippets = build_snippets_from_file(input).unwrap();
 
         assert_eq!(snippets.len(), 2);
         let snip = snippets.get(0).unwrap();
@@ -184,11 +190,28 @@ mod tests {
         assert_eq!(snip2.description, expected.get(1).unwrap().description);
     }
 
+    #[test]
+    fn test_body_line_before_any_header() {
+        let input: Vec<String> = vec![
+            "# Rust snippets".to_string(),
+            "".to_string(),
+            "\tlet x = 1;".to_string(),
+            "snippet test".to_string(),
+        ];
+
+        let err = build_snippets_from_file(input).unwrap_err();
+        assert!(matches!(err, TektonError::Syntax { line: Some(3), .. }));
+        assert_eq!(
+            err.in_file("rust.snippets").to_string(),
+            "Invalid snippets in `rust.snippets` at line 3: Not part of any snippet, expected a `snippet` header before it"
+        );
+    }
+
     #[test]
     fn test_output_string() {
         let input: Vec<String> = vec!["snippet test".to_string(), "   test snippet".to_string()];
 
-        let snippets = build_snippets_from_file(input);
+        let snippets = build_snippets_from_file(input).unwrap();
 
         if let Ok(res) = build_snipmate_string(snippets) {
             let spaces = "   "; // Note four spaces
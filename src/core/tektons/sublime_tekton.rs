@@ -0,0 +1,284 @@
+//! Functions related to reading and writing Sublime Text (`*.sublime-snippet`) snippets
+
+use super::friendly_tekton::build_friendly_string;
+use crate::{
+    errors::TektonError,
+    models::{
+        friendly::{FriendlySnippetBody, FriendlySnippets},
+        sublime::SublimeSnippet,
+    },
+    utils::{read_input, sanitize_filename, xml_unescape},
+};
+use regex::Regex;
+use std::{fs, path::Path};
+
+/// The file extension of a Sublime Text snippet
+pub const SUBLIME_EXTENSION: &str = "sublime-snippet";
+
+/// Languages that live under the `text.` scope in Sublime Text rather than `source.`
+const TEXT_SCOPES: [(&str, &str); 5] = [
+    ("html", "text.html"),
+    ("markdown", "text.html.markdown"),
+    ("plaintext", "text.plain"),
+    ("latex", "text.tex.latex"),
+    ("xml", "text.xml"),
+];
+
+/// Function to handle Sublime Text snippets to JSON
+///
+/// Arguments:
+/// - `path`: a single `*.sublime-snippet` file or a directory of them
+///
+/// Returns:
+/// - The converted JSON string representation or an error
+pub fn compose_friendly_from_sublime(path: &str) -> Result<String, TektonError> {
+    build_friendly_string(read_in_sublime_snippets(path)?)
+}
+
+/// A function to convert JSON snippets to Sublime Text snippets, one file per snippet
+///
+/// Arguments:
+/// - `friendlies`: the structure holding the table of snippets.
+///
+/// Returns:
+/// - Result of the file names and XML contents of each snippet or an error
+pub fn compose_sublime_snippets(
+    friendlies: FriendlySnippets,
+) -> Result<Vec<(String, String)>, TektonError> {
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection { path: None });
+    }
+
+    let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+    names.sort_by_key(|name| name.to_lowercase());
+
+    let mut files: Vec<(String, String)> = Vec::new();
+    for name in names {
+        let snippet = &friendlies.snippets[name];
+        let sublime = SublimeSnippet::new(
+            snippet.body.join("\n"),
+            snippet.prefix.clone(),
+            snippet.scope.as_deref().map(language_to_sublime_scope),
+            snippet.description.clone(),
+        );
+
+        let stem = sanitize_filename(name);
+        let mut file_name = format!("{}.{}", stem, SUBLIME_EXTENSION);
+        let mut count = 1;
+        while files.iter().any(|(existing, _)| existing == &file_name) {
+            count += 1;
+            file_name = format!("{} {}.{}", stem, count, SUBLIME_EXTENSION);
+        }
+        files.push((file_name, sublime.display()));
+    }
+
+    Ok(files)
+}
+
+/// A function to read a single Sublime Text snippet file, or a directory of them, as one collection
+///
+/// The snippet names are taken from the file names.
+///
+/// Arguments:
+/// - `path`: a single `*.sublime-snippet` file or a directory of them
+///
+/// Returns:
+/// - The snippets read in or an error
+pub fn read_in_sublime_snippets(path: &str) -> Result<FriendlySnippets, TektonError> {
+    let mut files: Vec<std::path::PathBuf> = Vec::new();
+    if Path::new(path).is_dir() {
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().and_then(|ext| ext.to_str()) == Some(SUBLIME_EXTENSION) {
+                files.push(file);
+            }
+        }
+        files.sort();
+    } else {
+        files.push(Path::new(path).to_path_buf());
+    }
+
+    let mut friendlies = FriendlySnippets::new();
+    for file in files {
+        let name = file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let sublime = build_sublime_snippet(&read_input(&file.to_string_lossy())?)
+            .map_err(|e| e.in_file(&file.to_string_lossy()))?;
+
+        let mut snippet = FriendlySnippetBody::new(
+            sublime.tab_trigger,
+            sublime
+                .content
+                .split('\n')
+                .map(|line| line.to_string())
+                .collect(),
+            sublime.description,
+        );
+        snippet.scope = sublime.scope.as_deref().map(sublime_scope_to_language);
+        friendlies.snippets.insert(name, snippet);
+    }
+
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection {
+            path: Some(path.to_string()),
+        });
+    }
+    Ok(friendlies)
+}
+
+/// Function to construct a SublimeSnippet struct from the contents of a `*.sublime-snippet` file
+///
+/// Arguments:
+/// - `xml`: the contents of the file
+///
+/// Returns:
+/// - The snippet, or an error if the file has no `<snippet>` or `<content>` element
+pub fn build_sublime_snippet(xml: &str) -> Result<SublimeSnippet, TektonError> {
+    let missing = |element: &str| TektonError::Syntax {
+        path: None,
+        line: None,
+        message: format!("Missing the `<{}>` element", element),
+    };
+    if !xml.contains("<snippet>") {
+        return Err(missing("snippet"));
+    }
+
+    let mut content = find_element(xml, "content").ok_or_else(|| missing("content"))?;
+    // The content is conventionally wrapped onto its own lines inside of the CDATA section
+    if let Some(stripped) = content.strip_prefix('\n') {
+        content = stripped.to_string();
+    }
+    if let Some(stripped) = content.strip_suffix('\n') {
+        content = stripped.to_string();
+    }
+
+    Ok(SublimeSnippet::new(
+        content,
+        find_element(xml, "tabTrigger"),
+        find_element(xml, "scope"),
+        find_element(xml, "description"),
+    ))
+}
+
+/// Helper to find the (unescaped) text of the first element with the given tag
+///
+/// CDATA sections are skipped over while looking for the closing tag, since they may contain it.
+fn find_element(xml: &str, tag: &str) -> Option<String> {
+    let open = Regex::new(&format!(r"<{}\s*>", tag)).unwrap();
+    let close = format!("</{}>", tag);
+    let start = open.find(xml)?.end();
+
+    let mut index = start;
+    loop {
+        let rest = &xml[index..];
+        let next_close = rest.find(&close)?;
+        match rest.find("<![CDATA[") {
+            Some(cdata) if cdata < next_close => {
+                let cdata_end = rest[cdata..].find("]]>")?;
+                index += cdata + cdata_end + "]]>".len();
+            }
+            _ => return Some(xml_unescape(xml[start..index + next_close].trim())),
+        }
+    }
+}
+
+/// Helper to translate a Sublime Text scope selector (`source.rust, text.html`) to language names (`rust,html`)
+fn sublime_scope_to_language(scope: &str) -> String {
+    scope
+        .split(',')
+        .map(|selector| {
+            let selector = selector.trim();
+            match TEXT_SCOPES.iter().find(|(_, sublime)| *sublime == selector) {
+                Some((language, _)) => language.to_string(),
+                None => selector.split('.').nth(1).unwrap_or(selector).to_string(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Helper to translate language names (`rust,html`) to a Sublime Text scope selector (`source.rust, text.html`)
+fn language_to_sublime_scope(languages: &str) -> String {
+    languages
+        .split(',')
+        .map(|language| {
+            let language = language.trim();
+            match TEXT_SCOPES.iter().find(|(name, _)| *name == language) {
+                Some((_, sublime)) => sublime.to_string(),
+                None if language.contains('.') => language.to_string(),
+                None => format!("source.{}", language),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUBLIME: &str = r#"<snippet>
+    <content><![CDATA[
+Hello, ${1:this} is a ${2:snippet}.
+]]></content>
+    <tabTrigger>hello</tabTrigger>
+    <scope>source.python, text.html</scope>
+    <description>Say &quot;hello&quot;</description>
+</snippet>"#;
+
+    #[test]
+    fn test_building_sublime_snippet() -> Result<(), TektonError> {
+        let snip = build_sublime_snippet(SUBLIME)?;
+
+        assert_eq!(snip.content, "Hello, ${1:this} is a ${2:snippet}.");
+        assert_eq!(snip.tab_trigger, Some("hello".to_string()));
+        assert_eq!(snip.scope, Some("source.python, text.html".to_string()));
+        assert_eq!(snip.description, Some("Say \"hello\"".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_content() {
+        assert!(build_sublime_snippet("<snippet></snippet>").is_err());
+        assert!(build_sublime_snippet("not xml").is_err());
+    }
+
+    #[test]
+    fn test_scope_translation() {
+        assert_eq!(
+            sublime_scope_to_language("source.python, text.html"),
+            "python,html"
+        );
+        assert_eq!(
+            language_to_sublime_scope("python,html"),
+            "source.python, text.html"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_through_display() -> Result<(), TektonError> {
+        let snip = build_sublime_snippet(SUBLIME)?;
+        let again = build_sublime_snippet(&snip.clone().display())?;
+
+        assert_eq!(snip, again);
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_json_to_sublime() -> Result<(), TektonError> {
+        let mut friendlies = FriendlySnippets::new();
+        let mut body =
+            FriendlySnippetBody::new(Some("pr".into()), vec!["print($1)".to_string()], None);
+        body.scope = Some("python".to_string());
+        friendlies.snippets.insert("Print/Line".into(), body);
+
+        let files = compose_sublime_snippets(friendlies)?;
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "Print_Line.sublime-snippet");
+        assert!(files[0].1.contains("<scope>source.python</scope>"));
+        Ok(())
+    }
+}
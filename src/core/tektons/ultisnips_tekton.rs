@@ -0,0 +1,438 @@
+//! Functions related to reading and writing UltiSnips snippets
+
+use super::friendly_tekton::{build_friendly_string, convert_snipmate_to_friendlysnippets};
+use crate::{
+    errors::TektonError,
+    models::{
+        friendly::FriendlySnippets,
+        naming::SnippetNaming,
+        snipmate::Snipmate,
+        ultisnips::{UltiSnip, UltiSnippets, ULTISNIPS_OPTIONS},
+    },
+};
+use regex::Regex;
+
+/// Function to handle UltiSnips to JSON
+///
+/// Anything that has no JSON equivalent (options, priorities, `extends`, `global` blocks, ...)
+/// is reported to the user before the conversion continues.
+///
+/// Arguments:
+/// - `lines`: the lines of UltiSnips snippets to be converted
+/// - `naming`: how to name the snippets
+///
+/// Returns:
+/// - The converted JSON string representation or an error
+pub fn compose_friendly_from_ultisnips(
+    lines: Vec<String>,
+    naming: &SnippetNaming,
+) -> Result<String, TektonError> {
+    let ultisnips = build_ultisnips_from_file(lines)?;
+    for warning in find_unconvertible(&ultisnips) {
+        eprintln!("[ WARN ]: {}", warning);
+    }
+
+    let snips: Vec<Snipmate> = ultisnips
+        .snippets
+        .into_iter()
+        .map(|snip| {
+            let body = snip
+                .body
+                .iter()
+                .map(|line| line.replace("\\`", "`"))
+                .collect();
+            Snipmate::new(snip.prefix, body, snip.description)
+        })
+        .collect();
+    let friendlies = convert_snipmate_to_friendlysnippets(snips, naming);
+    build_friendly_string(friendlies)
+}
+
+/// A function to convert JSON snippets to UltiSnips snippets
+///
+/// Arguments:
+/// - `friendlies`: the structure holding the table of snippets.
+///
+/// Returns:
+/// - Result of the composed UltiSnips string representation or an error
+pub fn compose_ultisnips_snippets(friendlies: FriendlySnippets) -> Result<String, TektonError> {
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection { path: None });
+    }
+
+    let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+    names.sort_by_key(|name| name.to_lowercase());
+
+    let mut snippets: Vec<String> = Vec::new();
+    for name in names {
+        let snippet = &friendlies.snippets[name];
+        match &snippet.prefix {
+            Some(prefix) => snippets.push(
+                UltiSnip::new(
+                    prefix.to_string(),
+                    // Backticks start an interpolation in UltiSnips, so they need escaping
+                    snippet
+                        .body
+                        .iter()
+                        .map(|line| line.replace('`', "\\`"))
+                        .collect(),
+                    snippet.description.clone(),
+                )
+                .display(),
+            ),
+            None => {
+                return Err(TektonError::MissingPrefix {
+                    path: None,
+                    snippet: name.to_string(),
+                })
+            }
+        }
+    }
+
+    Ok(snippets.join("\n"))
+}
+
+/// Function to construct the UltiSnips structs from the lines of an UltiSnips file.
+///
+/// Arguments:
+/// - `lines`: a vector with the UltiSnips source file read in as a vec of strings
+///
+/// Returns:
+/// - The snippets and file level directives, or an error for a malformed file
+pub fn build_ultisnips_from_file(lines: Vec<String>) -> Result<UltiSnippets, TektonError> {
+    let mut ultisnips = UltiSnippets::default();
+    let mut priority: Option<i64> = None;
+    let mut lines = lines.into_iter();
+    let mut number: usize = 0;
+
+    while let Some(line) = lines.next() {
+        number += 1;
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        let remain = line.trim_start()[keyword.len()..].trim();
+        let syntax = |message: String| TektonError::Syntax {
+            path: None,
+            line: Some(number),
+            message,
+        };
+        match keyword {
+            "snippet" => {
+                let mut snip = parse_snippet_header(remain).map_err(syntax)?;
+                snip.priority = priority;
+                snip.body = read_block(&mut lines, "endsnippet").ok_or_else(|| {
+                    syntax(format!(
+                        "Snippet `{}` is missing its `endsnippet`",
+                        snip.prefix
+                    ))
+                })?;
+                number += snip.body.len() + 1;
+                ultisnips.snippets.push(snip);
+            }
+            "global" => {
+                let block = read_block(&mut lines, "endglobal").ok_or_else(|| {
+                    syntax("A `global` block is missing its `endglobal`".to_string())
+                })?;
+                number += block.len() + 1;
+                ultisnips.globals += 1;
+            }
+            "priority" => match remain.parse::<i64>() {
+                Ok(value) => priority = Some(value),
+                Err(_) => return Err(syntax(format!("Invalid priority value: `{}`", remain))),
+            },
+            "extends" => ultisnips.extends.extend(
+                remain
+                    .split(',')
+                    .map(|filetype| filetype.trim().to_string())
+                    .filter(|filetype| !filetype.is_empty()),
+            ),
+            "clearsnippets" | "context" | "pre_expand" | "post_expand" | "post_jump" => {
+                ultisnips.directives.push(line.trim().to_string())
+            }
+            // Comments and blank lines between snippets
+            _ => {}
+        }
+    }
+
+    Ok(ultisnips)
+}
+
+/// Function to list everything in an UltiSnips file that can't be represented as JSON
+///
+/// Arguments:
+/// - `ultisnips`: the parsed UltiSnips file
+///
+/// Returns:
+/// - A list of human readable warnings (empty if everything converts)
+pub fn find_unconvertible(ultisnips: &UltiSnippets) -> Vec<String> {
+    let mut warnings: Vec<String> = Vec::new();
+    let interpolation = Regex::new(r"(^|[^\\])`").unwrap();
+
+    if !ultisnips.extends.is_empty() {
+        warnings.push(format!(
+            "`extends {}` has no JSON equivalent and was not converted",
+            ultisnips.extends.join(", ")
+        ));
+    }
+    if ultisnips.globals > 0 {
+        warnings.push(format!(
+            "{} `global` block(s) have no JSON equivalent and were not converted",
+            ultisnips.globals
+        ));
+    }
+    for directive in ultisnips.directives.iter() {
+        warnings.push(format!(
+            "`{}` has no JSON equivalent and was not converted",
+            directive
+        ));
+    }
+
+    for snip in ultisnips.snippets.iter() {
+        for option in snip.options.chars() {
+            let reason = match option {
+                'b' => "expand only at the beginning of a line",
+                'w' => "expand at word boundaries",
+                'i' => "expand in-word",
+                'r' => "regular expression trigger",
+                'A' => "automatic expansion",
+                _ => "option",
+            };
+            warnings.push(format!(
+                "Snippet `{}`: option `{}` ({}) has no JSON equivalent",
+                snip.prefix, option, reason
+            ));
+        }
+        if let Some(priority) = snip.priority {
+            warnings.push(format!(
+                "Snippet `{}`: `priority {}` has no JSON equivalent",
+                snip.prefix, priority
+            ));
+        }
+        if snip.body.iter().any(|line| interpolation.is_match(line)) {
+            warnings.push(format!(
+                "Snippet `{}`: contains a backtick interpolation that will be copied as plain text",
+                snip.prefix
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Helper to parse everything after the `snippet` keyword, following the rules UltiSnips uses:
+/// `trigger "description" options`, where the trigger is delimited when it contains whitespace
+/// or is a regular expression. An invalid header is described by the error message.
+fn parse_snippet_header(header: &str) -> Result<UltiSnip, String> {
+    let mut remain = header.trim().to_string();
+    let mut options = String::new();
+
+    let words: Vec<&str> = remain.split_whitespace().collect();
+    if words.len() > 2 {
+        let last = words[words.len() - 1];
+        let second_to_last = words[words.len() - 2];
+        if !last.contains('"') && second_to_last.ends_with('"') {
+            options = last.to_string();
+            remain = remain[..remain.len() - last.len()].trim_end().to_string();
+        }
+    }
+
+    let mut description: Option<String> = None;
+    if remain.split_whitespace().count() > 1 && remain.ends_with('"') {
+        // The description starts after the last quote before its closing one, like UltiSnips
+        if let Some(left) = remain[..remain.len() - 1].rfind('"') {
+            if left != 0 {
+                let descr = remain[left + 1..remain.len() - 1].to_string();
+                if !descr.is_empty() {
+                    description = Some(descr);
+                }
+                remain = remain[..left].to_string();
+            }
+        }
+    }
+
+    let mut prefix = remain.trim().to_string();
+    if prefix.is_empty() {
+        return Err("Found a `snippet` header without a trigger".to_string());
+    }
+    if prefix.split_whitespace().count() > 1 || options.contains('r') {
+        let mut chars = prefix.chars();
+        match (chars.next(), chars.next_back()) {
+            (Some(first), Some(last)) if first == last => {
+                prefix = chars.collect();
+            }
+            _ => return Err(format!("Invalid multiword trigger: `{}`", prefix)),
+        }
+    }
+
+    if let Some(option) = options.chars().find(|c| !ULTISNIPS_OPTIONS.contains(*c)) {
+        return Err(format!(
+            "Snippet `{}` has an unknown option `{}`",
+            prefix, option
+        ));
+    }
+
+    let mut snip = UltiSnip::new(prefix, Vec::new(), description);
+    snip.options = options;
+    Ok(snip)
+}
+
+/// Helper to collect the lines up to (and consuming) the `end` keyword
+fn read_block(lines: &mut impl Iterator<Item = String>, end: &str) -> Option<Vec<String>> {
+    let mut block: Vec<String> = Vec::new();
+    for line in lines {
+        if line.trim_end() == end {
+            return Some(block);
+        }
+        block.push(line);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::friendly::FriendlySnippetBody;
+
+    fn to_lines(file: &str) -> Vec<String> {
+        file.lines().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_building_ultisnips_from_file() {
+        let input = to_lines(
+            r#"# A comment
+priority -50
+extends c, cpp
+
+global !p
+def helper():
+    return 1
+endglobal
+
+snippet fn "A function" bA
+fn ${1:name}() {
+	$0
+}
+endsnippet
+
+snippet "two words" "Delimited trigger"
+x
+endsnippet
+
+snippet pr
+print!(`!p snip.rv = "\`"`);
+endsnippet"#,
+        );
+
+        let ultisnips = build_ultisnips_from_file(input).unwrap();
+
+        assert_eq!(ultisnips.extends, vec!["c".to_string(), "cpp".to_string()]);
+        assert_eq!(ultisnips.globals, 1);
+        assert_eq!(ultisnips.snippets.len(), 3);
+
+        let snip = &ultisnips.snippets[0];
+        assert_eq!(snip.prefix, "fn");
+        assert_eq!(snip.description, Some("A function".to_string()));
+        assert_eq!(snip.options, "bA");
+        assert_eq!(snip.priority, Some(-50));
+        assert_eq!(
+            snip.body,
+            ["fn ${1:name}() {", "\t$0", "}"]
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+        );
+
+        assert_eq!(ultisnips.snippets[1].prefix, "two words");
+        assert_eq!(ultisnips.snippets[2].description, None);
+
+        let warnings = find_unconvertible(&ultisnips);
+        // extends, global, 2 options, 3 priorities and the interpolation
+        assert_eq!(warnings.len(), 8);
+    }
+
+    #[test]
+    fn test_regex_trigger() {
+        let input =
+            to_lines("snippet !^(\\d+)x! \"Repeat\" r\n`!p snip.rv = match.group(1)`\nendsnippet");
+
+        let ultisnips = build_ultisnips_from_file(input).unwrap();
+
+        assert_eq!(ultisnips.snippets[0].prefix, "^(\\d+)x");
+        assert_eq!(ultisnips.snippets[0].options, "r");
+    }
+
+    #[test]
+    fn test_missing_endsnippet() {
+        let input = to_lines("snippet test\nbody");
+
+        assert!(build_ultisnips_from_file(input).is_err());
+    }
+
+    #[test]
+    fn test_quotes_in_a_description_are_not_escapes() {
+        // UltiSnips has no escape for a quote, so the description is the text after the last one
+        let input = to_lines("snippet pr \"Say \\\"hi\\\"\" b\nendsnippet");
+
+        assert_eq!(
+            build_ultisnips_from_file(input).unwrap_err().to_string(),
+            "Invalid snippets at line 1: Invalid multiword trigger: `pr \"Say \\\"hi\\`"
+        );
+    }
+
+    #[test]
+    fn test_error_has_the_line_of_the_snippet() {
+        let input =
+            to_lines("priority 1\nsnippet a\nfirst\nendsnippet\n\nsnippet b \"\" Z\nendsnippet");
+
+        let err = build_ultisnips_from_file(input).unwrap_err();
+        assert!(matches!(err, TektonError::Syntax { line: Some(6), .. }));
+        assert_eq!(
+            err.to_string(),
+            "Invalid snippets at line 6: Snippet `b` has an unknown option `Z`"
+        );
+    }
+
+    #[test]
+    fn test_convert_json_to_ultisnips() -> Result<(), TektonError> {
+        let mut friendlies = FriendlySnippets::new();
+        friendlies.snippets.insert(
+            "beta".into(),
+            FriendlySnippetBody::new(
+                Some("b".into()),
+                vec!["second".to_string()],
+                Some("The \"second\" one".into()),
+            ),
+        );
+        friendlies.snippets.insert(
+            "alpha".into(),
+            FriendlySnippetBody::new(Some("a".into()), vec!["first".to_string()], None),
+        );
+
+        let string_rep = compose_ultisnips_snippets(friendlies)?;
+
+        assert_eq!(
+            string_rep,
+            "snippet a\nfirst\nendsnippet\n\nsnippet b \"The 'second' one\"\nsecond\nendsnippet\n"
+        );
+        let ultisnips = build_ultisnips_from_file(to_lines(&string_rep))?;
+        assert_eq!(ultisnips.snippets[1].prefix, "b");
+        assert_eq!(
+            ultisnips.snippets[1].description,
+            Some("The 'second' one".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_round_trip_header() {
+        let mut snip = UltiSnip::new(
+            "two words".to_string(),
+            vec!["x".to_string()],
+            Some("A description".to_string()),
+        );
+        snip.options = "b".to_string();
+
+        let ultisnips = build_ultisnips_from_file(to_lines(&snip.clone().display())).unwrap();
+
+        assert_eq!(ultisnips.snippets, vec![snip]);
+    }
+}
@@ -0,0 +1,246 @@
+//! Functions related to reading and writing Emacs yasnippet snippet directories
+
+use super::friendly_tekton::build_friendly_string;
+use crate::{
+    errors::TektonError,
+    models::{
+        friendly::{FriendlySnippetBody, FriendlySnippets},
+        yasnippet::YaSnippet,
+    },
+    utils::{read_input, sanitize_filename},
+};
+use regex::Regex;
+use std::{fs, path::Path};
+
+/// Function to handle a yasnippet `<mode>/` directory to JSON
+///
+/// Arguments:
+/// - `path`: the `<mode>/` directory with one file per snippet
+///
+/// Returns:
+/// - The converted JSON string representation or an error
+pub fn compose_friendly_from_yasnippet(path: &str) -> Result<String, TektonError> {
+    build_friendly_string(read_in_yasnippet_snippets(path)?)
+}
+
+/// A function to convert JSON snippets to yasnippet snippets, one file per snippet
+///
+/// The snippet name is used as the file name and the description (if any) as the `# name:`
+/// shown in the yasnippet menus.
+///
+/// Arguments:
+/// - `friendlies`: the structure holding the table of snippets.
+///
+/// Returns:
+/// - Result of the file names and contents of each snippet or an error
+pub fn compose_yasnippet_snippets(
+    friendlies: FriendlySnippets,
+) -> Result<Vec<(String, String)>, TektonError> {
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection { path: None });
+    }
+
+    let mut names: Vec<&String> = friendlies.snippets.keys().collect();
+    names.sort_by_key(|name| name.to_lowercase());
+
+    let mut files: Vec<(String, String)> = Vec::new();
+    for name in names {
+        let snippet = &friendlies.snippets[name];
+        let menu_name = match &snippet.description {
+            Some(description) if !description.is_empty() => description.replace('\n', " "),
+            _ => name.to_string(),
+        };
+        // Backquotes are evaluated as Emacs Lisp by yasnippet, so they need escaping
+        let body = snippet
+            .body
+            .iter()
+            .map(|line| line.replace('`', "\\`"))
+            .collect();
+        let yasnippet = YaSnippet::new(menu_name, snippet.prefix.clone(), body);
+
+        let stem = sanitize_filename(name);
+        let mut file_name = stem.clone();
+        let mut count = 1;
+        while files.iter().any(|(existing, _)| existing == &file_name) {
+            count += 1;
+            file_name = format!("{} {}", stem, count);
+        }
+        files.push((file_name, yasnippet.display()));
+    }
+
+    Ok(files)
+}
+
+/// A function to read a yasnippet `<mode>/` directory as one collection
+///
+/// The snippet names are taken from the file names, and the `# name:` is kept as the description
+/// when it says something more than the file name.
+///
+/// Arguments:
+/// - `path`: the `<mode>/` directory with one file per snippet
+///
+/// Returns:
+/// - The snippets read in or an error
+pub fn read_in_yasnippet_snippets(path: &str) -> Result<FriendlySnippets, TektonError> {
+    let mut files: Vec<std::path::PathBuf> = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let file = entry?.path();
+        let hidden = file
+            .file_name()
+            .map(|name| name.to_string_lossy().starts_with('.'))
+            .unwrap_or(true);
+        if file.is_file() && !hidden {
+            files.push(file);
+        }
+    }
+    files.sort();
+
+    let parents = Path::new(path).join(".yas-parents");
+    if parents.is_file() {
+        eprintln!(
+            "[ WARN ]: `.yas-parents` ({}) has no JSON equivalent and was not converted",
+            fs::read_to_string(parents)?.trim()
+        );
+    }
+
+    let interpolation = Regex::new(r"(^|[^\\])`").unwrap();
+    let mut friendlies = FriendlySnippets::new();
+    for file in files {
+        let name = file
+            .file_name()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let yasnippet = build_yasnippet(&read_input(&file.to_string_lossy())?, &name);
+
+        for (directive, value) in yasnippet.directives.iter() {
+            eprintln!(
+                "[ WARN ]: Snippet `{}`: `# {}: {}` has no JSON equivalent",
+                name, directive, value
+            );
+        }
+        if yasnippet
+            .body
+            .iter()
+            .any(|line| interpolation.is_match(line))
+        {
+            eprintln!(
+                "[ WARN ]: Snippet `{}`: contains a backquote Emacs Lisp expression that will be copied as plain text",
+                name
+            );
+        }
+
+        let description = match yasnippet.name == name {
+            true => None,
+            false => Some(yasnippet.name),
+        };
+        let body = yasnippet
+            .body
+            .iter()
+            .map(|line| line.replace("\\`", "`"))
+            .collect();
+        friendlies.snippets.insert(
+            name,
+            FriendlySnippetBody::new(yasnippet.key, body, description),
+        );
+    }
+
+    if friendlies.snippets.is_empty() {
+        return Err(TektonError::EmptyCollection {
+            path: Some(path.to_string()),
+        });
+    }
+    Ok(friendlies)
+}
+
+/// Function to construct a YaSnippet struct from the contents of a snippet file
+///
+/// Arguments:
+/// - `file`: the contents of the file
+/// - `file_name`: the name of the file, used when there is no `# name:` directive
+///
+/// Returns:
+/// - The snippet (a file without a `# --` line is all body)
+pub fn build_yasnippet(file: &str, file_name: &str) -> YaSnippet {
+    let file = file.strip_suffix('\n').unwrap_or(file);
+    let lines: Vec<&str> = file.split('\n').collect();
+    let mut yasnippet = YaSnippet::new(file_name.to_string(), None, Vec::new());
+
+    let separator = lines.iter().position(|line| line.trim_end() == "# --");
+    let (header, body) = match separator {
+        Some(index) => (&lines[..index], &lines[index + 1..]),
+        None => (&lines[..0], &lines[..]),
+    };
+
+    let directive = Regex::new(r"^#\s*([\w-]+)\s*:\s*(.*?)\s*$").unwrap();
+    for line in header {
+        if let Some(captures) = directive.captures(line) {
+            let value = captures[2].to_string();
+            match &captures[1] {
+                "name" => yasnippet.name = value,
+                "key" => yasnippet.key = Some(value),
+                other => yasnippet.directives.push((other.to_string(), value)),
+            }
+        }
+    }
+    yasnippet.body = body.iter().map(|line| line.to_string()).collect();
+
+    yasnippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_building_yasnippet() {
+        let file = "# -*- mode: snippet -*-\n# name: for loop\n# key: for\n# group: loops\n# --\nfor ${1:x} in ${2:xs} {\n    $0\n}\n";
+
+        let snip = build_yasnippet(file, "for");
+
+        assert_eq!(snip.name, "for loop");
+        assert_eq!(snip.key, Some("for".to_string()));
+        assert_eq!(
+            snip.directives,
+            vec![("group".to_string(), "loops".to_string())]
+        );
+        assert_eq!(snip.body, vec!["for ${1:x} in ${2:xs} {", "    $0", "}"]);
+    }
+
+    #[test]
+    fn test_building_yasnippet_without_header() {
+        let snip = build_yasnippet("just a body", "plain");
+
+        assert_eq!(snip.name, "plain");
+        assert_eq!(snip.key, None);
+        assert_eq!(snip.body, vec!["just a body"]);
+    }
+
+    #[test]
+    fn test_convert_json_to_yasnippet() -> Result<(), TektonError> {
+        let mut friendlies = FriendlySnippets::new();
+        friendlies.snippets.insert(
+            "print".into(),
+            FriendlySnippetBody::new(
+                Some("pr".into()),
+                vec!["print(`${1}`)".to_string()],
+                Some("Print a line".into()),
+            ),
+        );
+
+        let files = compose_yasnippet_snippets(friendlies)?;
+
+        assert_eq!(
+            files,
+            vec![(
+                "print".to_string(),
+                "# -*- mode: snippet -*-\n# name: Print a line\n# key: pr\n# --\nprint(\\`${1}\\`)"
+                    .to_string()
+            )]
+        );
+
+        let snip = build_yasnippet(&files[0].1, &files[0].0);
+        assert_eq!(snip.name, "Print a line");
+        assert_eq!(snip.key, Some("pr".to_string()));
+        Ok(())
+    }
+}
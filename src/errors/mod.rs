@@ -2,33 +2,197 @@
 use core::fmt;
 use std::io;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+/// The errors of tekton, with the file (and line) they happened at where it is known
+///
+/// It is neither `Clone` nor `PartialEq` because the `io::Error` and `serde_json::Error` kept as
+/// the source of an error are neither, match on the variants to check for an error instead.
+#[derive(Debug)]
 pub enum TektonError {
     /// An error with a custom message as a String
     Reason(String),
-    /// An 'error state' that indicates the file needs to process with the `multi_prefix` support
-    SwitchModes(bool),
+    /// A file (or stdin and stdout, as `-`) could not be read or written
+    Io {
+        path: Option<String>,
+        source: io::Error,
+    },
+    /// The snippets are not valid JSON, the line and column are where serde_json stopped
+    Parse {
+        path: Option<String>,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+    /// A snippet file (e.g. UltiSnips, Sublime Text or Snipmate) could not be parsed, the line is
+    /// where the problem was found
+    Syntax {
+        path: Option<String>,
+        line: Option<usize>,
+        message: String,
+    },
+    /// A snippet has no prefix, which every format but JSON needs
+    MissingPrefix {
+        path: Option<String>,
+        snippet: String,
+    },
+    /// There is no conversion between the two formats
+    UnsupportedMapping { from: String, to: String },
+    /// There are no snippets to convert or sort
+    EmptyCollection { path: Option<String> },
+}
+
+impl TektonError {
+    /// Adds the file the error happened in, when it isn't known yet
+    ///
+    /// Arguments:
+    /// - `fname`: the file that was being read or written
+    ///
+    /// Returns:
+    /// - The same error, with its `path` set if it has one
+    pub fn in_file(self, fname: &str) -> Self {
+        match self {
+            TektonError::Io { path: None, source } => TektonError::Io {
+                path: Some(fname.to_string()),
+                source,
+            },
+            TektonError::Parse {
+                path: None,
+                line,
+                column,
+                source,
+            } => TektonError::Parse {
+                path: Some(fname.to_string()),
+                line,
+                column,
+                source,
+            },
+            TektonError::Syntax {
+                path: None,
+                line,
+                message,
+            } => TektonError::Syntax {
+                path: Some(fname.to_string()),
+                line,
+                message,
+            },
+            TektonError::MissingPrefix {
+                path: None,
+                snippet,
+            } => TektonError::MissingPrefix {
+                path: Some(fname.to_string()),
+                snippet,
+            },
+            TektonError::EmptyCollection { path: None } => TektonError::EmptyCollection {
+                path: Some(fname.to_string()),
+            },
+            other => other,
+        }
+    }
 }
 
 impl fmt::Display for TektonError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let string = match self {
-            TektonError::Reason(r) => r.clone(),
-            TektonError::SwitchModes(b) => b.to_string(),
-        };
-        write!(f, "{}", string)
+        match self {
+            TektonError::Reason(r) => write!(f, "{}", r),
+            TektonError::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "Unable to access `{}`: {}", path, source),
+            TektonError::Io { path: None, source } => write!(f, "{}", source),
+            TektonError::Parse {
+                path,
+                line,
+                column,
+                source,
+            } => {
+                // serde_json ends its messages with the position, which is given here instead
+                let message = source.to_string();
+                let position = format!(" at line {} column {}", line, column);
+                let message = message.strip_suffix(&position).unwrap_or(&message);
+                match path {
+                    Some(path) => write!(
+                        f,
+                        "Invalid JSON in `{}` at line {}, column {}: {}",
+                        path, line, column, message
+                    ),
+                    None => write!(
+                        f,
+                        "Invalid JSON at line {}, column {}: {}",
+                        line, column, message
+                    ),
+                }
+            }
+            TektonError::Syntax {
+                path,
+                line,
+                message,
+            } => match (path, line) {
+                (Some(path), Some(line)) => {
+                    write!(
+                        f,
+                        "Invalid snippets in `{}` at line {}: {}",
+                        path, line, message
+                    )
+                }
+                (Some(path), None) => write!(f, "Invalid snippets in `{}`: {}", path, message),
+                (None, Some(line)) => write!(f, "Invalid snippets at line {}: {}", line, message),
+                (None, None) => write!(f, "Invalid snippets: {}", message),
+            },
+            TektonError::MissingPrefix {
+                path: Some(path),
+                snippet,
+            } => write!(
+                f,
+                "Snippet `{}` in `{}` is missing a prefix, conversion aborted.",
+                snippet, path
+            ),
+            TektonError::MissingPrefix {
+                path: None,
+                snippet,
+            } => write!(
+                f,
+                "Snippet `{}` is missing a prefix, conversion aborted.",
+                snippet
+            ),
+            TektonError::UnsupportedMapping { from, to } => write!(
+                f,
+                "Converting `{}` snippets to `{}` is not supported",
+                from, to
+            ),
+            TektonError::EmptyCollection { path: Some(path) } => {
+                write!(f, "No snippets found in `{}`", path)
+            }
+            TektonError::EmptyCollection { path: None } => write!(f, "No snippets to convert"),
+        }
     }
 }
 
 impl std::error::Error for TektonError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        match self {
+            TektonError::Io { source, .. } => Some(source),
+            TektonError::Parse { source, .. } => Some(source),
+            _ => None,
+        }
     }
 }
 
 impl std::convert::From<io::Error> for TektonError {
     fn from(io_err: io::Error) -> Self {
-        TektonError::Reason(io_err.to_string())
+        TektonError::Io {
+            path: None,
+            source: io_err,
+        }
+    }
+}
+
+impl std::convert::From<serde_json::Error> for TektonError {
+    fn from(json_err: serde_json::Error) -> Self {
+        TektonError::Parse {
+            path: None,
+            line: json_err.line(),
+            column: json_err.column(),
+            source: json_err,
+        }
     }
 }
 
@@ -41,7 +205,57 @@ fn test_tekton_error_enum() {
 }
 
 #[test]
-fn test_tekton_switch() {
-    let switch = TektonError::SwitchModes(true);
-    assert_eq!(switch, TektonError::SwitchModes(true));
+fn test_parse_error_location() {
+    use std::error::Error;
+
+    let json_err = serde_json::from_str::<serde_json::Value>("{\n  \"a\": 1,\n}").unwrap_err();
+    let err = TektonError::from(json_err).in_file("rust.json");
+
+    assert!(matches!(
+        err,
+        TektonError::Parse {
+            line: 3,
+            column: 1,
+            ..
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Invalid JSON in `rust.json` at line 3, column 1: trailing comma"
+    );
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn test_in_file_keeps_a_known_path() {
+    let err = TektonError::EmptyCollection {
+        path: Some("rust.json".to_string()),
+    };
+
+    assert_eq!(
+        err.in_file("other.json").to_string(),
+        "No snippets found in `rust.json`"
+    );
+}
+
+#[test]
+fn test_in_file_locates_syntax_and_missing_prefix_errors() {
+    let syntax = TektonError::Syntax {
+        path: None,
+        line: Some(4),
+        message: "Snippet `fn` is missing its `endsnippet`".to_string(),
+    };
+    let missing = TektonError::MissingPrefix {
+        path: None,
+        snippet: "Print".to_string(),
+    };
+
+    assert_eq!(
+        syntax.in_file("rust.snippets").to_string(),
+        "Invalid snippets in `rust.snippets` at line 4: Snippet `fn` is missing its `endsnippet`"
+    );
+    assert_eq!(
+        missing.in_file("rust.json").to_string(),
+        "Snippet `Print` in `rust.json` is missing a prefix, conversion aborted."
+    );
 }
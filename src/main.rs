@@ -1,16 +1,22 @@
-extern crate walkdir;
 use clap::Parser;
 use tekton::core::handlers::convert_handler::convert_handler;
+use tekton::core::handlers::lint_handler::lint_handler;
 use tekton::core::handlers::sort_handler::sort_handler;
 use tekton::errors::TektonError;
 use tekton::models::args::{TektonArgs, TektonEntity};
 
 /// Entry point to the CLI App
-fn main() -> Result<(), TektonError> {
+fn main() {
     let args = TektonArgs::parse();
 
-    match args.entity_type {
+    let result: Result<(), TektonError> = match args.entity_type {
         TektonEntity::Convert(convert) => convert_handler(convert),
         TektonEntity::Sort(sort) => sort_handler(sort),
+        TektonEntity::Lint(lint) => lint_handler(lint),
+    };
+
+    if let Err(e) = result {
+        eprintln!("[Tekton Error]: {}", e);
+        std::process::exit(1);
     }
 }
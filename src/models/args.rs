@@ -1,6 +1,11 @@
 //! The commands for the tekton cli program
 extern crate clap;
 use clap::{Args, Parser, Subcommand};
+use std::num::NonZeroUsize;
+
+use super::{
+    collation::Collation, format::Format, naming::NameSource, repair::EditFormat, sort::SortKey,
+};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about, long_about = None)]
@@ -15,22 +20,204 @@ pub enum TektonEntity {
     Convert(ConversionCommand),
     /// Sorting JSON
     Sort(SortCommand),
+    /// Check JSON and Snipmate snippets for problems
+    Lint(LintCommand),
 }
 
 #[derive(Debug, Args)]
 pub struct ConversionCommand {
-    /// The input filename
+    /// The input filename, or `-` for stdin
     pub input_filename: String,
-    /// The output filename
+    /// The output filename, or `-` for stdout
     pub output_filename: String,
+    /// The format of the input, instead of the one of its extension (needed for stdin)
+    #[clap(long, value_enum)]
+    pub from: Option<Format>,
+    /// The format of the output, instead of the one of its extension (needed for stdout)
+    #[clap(long, value_enum)]
+    pub to: Option<Format>,
+    /// Write one file per language of the snippet `scope`, named after the language
+    /// (e.g. `out/all.snippet` -> `out/rust.snippet`), snippets without a scope use the output filename
+    #[clap(long)]
+    pub split_by_scope: bool,
+    /// How to name the Snipmate and UltiSnips snippets converted to JSON
+    #[clap(long, value_enum, default_value_t)]
+    pub name_by: NameSource,
+    /// Name the snippets converted to JSON with a template of `{prefix}` and `{description}`
+    /// (e.g. `"{prefix} - {description}"`), instead of `--name-by`
+    #[clap(long, value_name = "TEMPLATE", conflicts_with = "name_by")]
+    pub name_template: Option<String>,
+    /// A JSON file of prefixes and the names to give their snippets (e.g. `{"fn": "Function"}`),
+    /// the other snippets are named by `--name-by` or `--name-template`
+    #[clap(long, value_name = "FILE")]
+    pub name_map: Option<String>,
+    /// A TOML (`*.toml`) or JSON file of snippet names and the prefixes to give them,
+    /// for the JSON snippets missing a prefix
+    #[clap(long, value_name = "FILE")]
+    pub answers: Option<String>,
+    /// Write the JSON snippets missing a prefix to an answers file (TOML for `*.toml`, or JSON)
+    /// to fill in for `--answers`, instead of converting
+    #[clap(long, value_name = "FILE")]
+    pub emit_answers: Option<String>,
+    /// Fix the JSON snippets missing a prefix in `$VISUAL` or `$EDITOR` (as a `json` document, or
+    /// `toml` with `--editor=toml`), instead of prompting for the prefix
+    #[clap(long, value_enum, value_name = "FORMAT", num_args = 0..=1, require_equals = true, default_missing_value = "json")]
+    pub editor: Option<EditFormat>,
 }
 
 #[derive(Debug, Args)]
 pub struct SortCommand {
-    /// Path to the snippets file or directory to crawl
+    /// Path to the snippets file or directory to crawl, or `-` to sort stdin to stdout
     pub path: String,
-    /// If present, then the program will prompt for user input.
-    pub interactive: Option<String>,
-    /// If present, then the path should be to a directory
-    pub crawl: Option<String>,
+    /// Deprecated, use `--interactive` (only the value `yes` enables it)
+    #[clap(hide = true, value_name = "INTERACTIVE")]
+    pub legacy_interactive: Option<String>,
+    /// Deprecated, use `--recursive` (any value enables it)
+    #[clap(hide = true, value_name = "CRAWL")]
+    pub legacy_crawl: Option<String>,
+    /// Prompt to fix the files that can't be sorted as they are
+    #[clap(short, long, conflicts_with = "dry_run")]
+    pub interactive: bool,
+    /// Walk the directory recursively for snippet files
+    #[clap(short, long)]
+    pub recursive: bool,
+    /// Only sort the files matching this glob (e.g. `*.json`), can be repeated
+    #[clap(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+    /// Skip the files matching this glob (e.g. `node_modules/**`), can be repeated
+    #[clap(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+    /// List the files that sorting would change without writing to them
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Keep a copy of every file as `<file>.bak` before writing the sorted snippets
+    #[clap(long)]
+    pub backup: bool,
+    /// A TOML (`*.toml`) or JSON file of snippet names and the prefixes to give them,
+    /// for the snippets missing a prefix
+    #[clap(long, value_name = "FILE")]
+    pub answers: Option<String>,
+    /// Write the snippets missing a prefix to an answers file (TOML for `*.toml`, or JSON)
+    /// to fill in for `--answers`, instead of sorting
+    #[clap(long, value_name = "FILE")]
+    pub emit_answers: Option<String>,
+    /// With `--interactive`, fix the snippets missing a prefix in `$VISUAL` or `$EDITOR`
+    /// (as a `json` document, or `toml` with `--editor=toml`) instead of prompting for the prefix
+    #[clap(long, value_enum, value_name = "FORMAT", num_args = 0..=1, require_equals = true, default_missing_value = "json")]
+    pub editor: Option<EditFormat>,
+    /// Only print the warnings and errors
+    #[clap(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Print every file as it is processed, and the time each file took to sort
+    #[clap(short, long)]
+    pub verbose: bool,
+    /// The number of files to sort at once, defaults to the number of CPUs (interactive mode uses 1)
+    #[clap(short, long, value_name = "N")]
+    pub jobs: Option<NonZeroUsize>,
+    /// Only check that the files are sorted, listing the ones that would change (nothing is written)
+    #[clap(long)]
+    pub check: bool,
+    /// Print a unified diff of what sorting would change (nothing is written)
+    #[clap(long)]
+    pub diff: bool,
+    /// The order to sort the snippet names in
+    #[clap(long, value_enum, default_value_t = Collation::CaseInsensitive)]
+    pub collation: Collation,
+    /// The part of the snippets to sort them by
+    #[clap(long, value_enum, default_value_t = SortKey::Name)]
+    pub by: SortKey,
+    /// A field (e.g. `scope`) to group the snippets by before sorting them
+    #[clap(long)]
+    pub group_by: Option<String>,
+}
+
+impl SortCommand {
+    /// If the user is prompted to fix the files, from `--interactive` or the old `yes` argument
+    pub fn is_interactive(&self) -> bool {
+        self.interactive || self.legacy_interactive.as_deref() == Some("yes")
+    }
+
+    /// If the directories are walked, from `--recursive` or the old `CRAWL` argument
+    pub fn is_recursive(&self) -> bool {
+        self.recursive || self.legacy_crawl.is_some()
+    }
+
+    /// If the deprecated positional `INTERACTIVE` or `CRAWL` arguments were used
+    pub fn uses_legacy_arguments(&self) -> bool {
+        self.legacy_interactive.is_some() || self.legacy_crawl.is_some()
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct LintCommand {
+    /// Path to the snippets file, or a directory to crawl for them
+    pub path: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort_command(args: &[&str]) -> SortCommand {
+        let args = TektonArgs::try_parse_from(["tekton", "sort"].iter().chain(args)).unwrap();
+        match args.entity_type {
+            TektonEntity::Sort(sort) => sort,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_sort_flags() {
+        let sort = sort_command(&["dir", "-r", "--interactive", "--exclude", "*.lua"]);
+
+        assert!(sort.is_recursive());
+        assert!(sort.is_interactive());
+        assert!(!sort.uses_legacy_arguments());
+        assert_eq!(sort.exclude, vec!["*.lua".to_string()]);
+    }
+
+    #[test]
+    fn test_legacy_positional_arguments() {
+        let crawl = sort_command(&["dir", "no", "true"]);
+        assert!(crawl.is_recursive());
+        assert!(!crawl.is_interactive());
+        assert!(crawl.uses_legacy_arguments());
+
+        let interactive = sort_command(&["file.json", "yes"]);
+        assert!(!interactive.is_recursive());
+        assert!(interactive.is_interactive());
+    }
+
+    #[test]
+    fn test_convert_formats() {
+        let args = TektonArgs::try_parse_from([
+            "tekton", "convert", "--from", "vscode", "--to", "snippet", "-", "-",
+        ])
+        .unwrap();
+        let convert = match args.entity_type {
+            TektonEntity::Convert(convert) => convert,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(convert.from, Some(Format::Json));
+        assert_eq!(convert.to, Some(Format::Snipmate));
+        assert_eq!(convert.input_filename, "-");
+    }
+
+    #[test]
+    fn test_editor_does_not_take_the_path() {
+        let sort = sort_command(&["-i", "--editor", "file.json"]);
+        assert_eq!(sort.path, "file.json");
+        assert_eq!(sort.editor, Some(EditFormat::Json));
+
+        let sort = sort_command(&["file.json", "-i", "--editor=toml"]);
+        assert_eq!(sort.editor, Some(EditFormat::Toml));
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_verbose() {
+        let args = TektonArgs::try_parse_from(["tekton", "sort", "file.json", "-q", "-v"]);
+
+        assert!(args.is_err());
+    }
 }
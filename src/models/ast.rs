@@ -0,0 +1,278 @@
+//! Structures to model the body of a snippet (the LSP/TextMate snippet grammar) as a syntax tree
+
+/// A parsed snippet body
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnippetAst {
+    /// The markers of the body, in the order they appear
+    pub markers: Vec<Marker>,
+}
+
+/// A single piece of a snippet body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    /// Plain text, with any escapes (`\$`, `\}`, `\\`) resolved
+    Text(String),
+    /// `$1`, `${1}` or `${1/regex/format/options}`
+    Tabstop {
+        index: usize,
+        transform: Option<Transform>,
+    },
+    /// `${1:default}`, where the default may hold other markers
+    Placeholder { index: usize, children: Vec<Marker> },
+    /// `${1|one,two,three|}`
+    Choice { index: usize, options: Vec<String> },
+    /// `$NAME`, `${NAME}`, `${NAME:default}` or `${NAME/regex/format/options}`
+    Variable {
+        name: String,
+        default: Option<Vec<Marker>>,
+        transform: Option<Transform>,
+    },
+}
+
+/// A regular expression transform (`/regex/format/options`) of a tabstop or variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transform {
+    /// The regular expression, as written (only `\/` is unescaped)
+    pub regex: String,
+    /// The replacement
+    pub format: Vec<FormatItem>,
+    /// The regular expression flags (e.g. `gi`)
+    pub options: String,
+}
+
+/// A single piece of the replacement of a `Transform`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatItem {
+    /// Plain text, with any escapes resolved
+    Text(String),
+    /// `$1` or `${1}`
+    Group(usize),
+    /// `${1:/upcase}`, `${1:/downcase}`, `${1:/capitalize}`, `${1:/camelcase}` or `${1:/pascalcase}`
+    Modifier { group: usize, modifier: String },
+    /// `${1:+if}`, `${1:?if:else}`, `${1:-else}` or `${1:else}`
+    Conditional {
+        group: usize,
+        if_value: Option<String>,
+        else_value: Option<String>,
+    },
+}
+
+impl SnippetAst {
+    /// Converts the syntax tree back to the snippet syntax
+    pub fn display(&self) -> String {
+        display_markers(&self.markers, &['$'])
+    }
+
+    /// Calls `visit` on every marker of the body, including the ones nested in defaults
+    pub fn walk(&self, visit: &mut impl FnMut(&Marker)) {
+        walk_markers(&self.markers, visit);
+    }
+
+    /// The indices of the tabstops, placeholders and choices of the body, sorted and without duplicates
+    pub fn tabstops(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = Vec::new();
+        self.walk(&mut |marker| match marker {
+            Marker::Tabstop { index, .. }
+            | Marker::Placeholder { index, .. }
+            | Marker::Choice { index, .. } => indices.push(*index),
+            _ => {}
+        });
+        indices.sort();
+        indices.dedup();
+        indices
+    }
+}
+
+impl Marker {
+    /// The text the marker expands to when every placeholder keeps its default
+    ///
+    /// Choices use their first option, and tabstops and variables (which can't be known ahead of time)
+    /// expand to their default or nothing.
+    pub fn plain_text(&self) -> String {
+        match self {
+            Marker::Text(text) => text.clone(),
+            Marker::Tabstop { .. } => String::new(),
+            Marker::Placeholder { children, .. } => plain_text(children),
+            Marker::Choice { options, .. } => options.first().cloned().unwrap_or_default(),
+            Marker::Variable { default, .. } => default
+                .as_ref()
+                .map(|children| plain_text(children))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Converts the marker to the snippet syntax
+    ///
+    /// Arguments:
+    /// - `specials`: the characters that need escaping in text (`$`, and `}` inside of a default)
+    fn display(&self, specials: &[char]) -> String {
+        match self {
+            Marker::Text(text) => escape(text, specials),
+            Marker::Tabstop {
+                index,
+                transform: None,
+            } => format!("${}", index),
+            Marker::Tabstop {
+                index,
+                transform: Some(transform),
+            } => format!("${{{}{}}}", index, transform.display()),
+            Marker::Placeholder { index, children } => {
+                format!("${{{}:{}}}", index, display_markers(children, &['$', '}']))
+            }
+            Marker::Choice { index, options } => {
+                let options: Vec<String> = options
+                    .iter()
+                    .map(|option| escape(option, &['$', '}', ',', '|']))
+                    .collect();
+                format!("${{{}|{}|}}", index, options.join(","))
+            }
+            Marker::Variable {
+                name,
+                default: None,
+                transform: None,
+            } => format!("${{{}}}", name),
+            Marker::Variable {
+                name,
+                default: Some(children),
+                ..
+            } => format!("${{{}:{}}}", name, display_markers(children, &['$', '}'])),
+            Marker::Variable {
+                name,
+                transform: Some(transform),
+                ..
+            } => format!("${{{}{}}}", name, transform.display()),
+        }
+    }
+}
+
+impl Transform {
+    /// Converts the transform to the snippet syntax (`/regex/format/options`)
+    pub fn display(&self) -> String {
+        let format: String = self.format.iter().map(|item| item.display()).collect();
+        format!(
+            "/{}/{}/{}",
+            self.regex.replace('/', "\\/"),
+            format,
+            self.options
+        )
+    }
+}
+
+impl FormatItem {
+    /// Converts the format item to the snippet syntax
+    pub fn display(&self) -> String {
+        match self {
+            FormatItem::Text(text) => escape(text, &['$', '}', '/']),
+            FormatItem::Group(group) => format!("${}", group),
+            FormatItem::Modifier { group, modifier } => format!("${{{}:/{}}}", group, modifier),
+            FormatItem::Conditional {
+                group,
+                if_value,
+                else_value,
+            } => {
+                let if_text = if_value.as_deref().map(|text| escape(text, &['}', ':']));
+                let else_text = else_value.as_deref().map(|text| escape(text, &['}']));
+                match (if_text, else_text) {
+                    (Some(if_text), Some(else_text)) => {
+                        format!("${{{}:?{}:{}}}", group, if_text, else_text)
+                    }
+                    (Some(if_text), None) => format!("${{{}:+{}}}", group, if_text),
+                    (None, Some(else_text)) => format!("${{{}:-{}}}", group, else_text),
+                    (None, None) => format!("${{{}}}", group),
+                }
+            }
+        }
+    }
+}
+
+/// Helper to get the text a list of markers expands to when every placeholder keeps its default
+pub fn plain_text(markers: &[Marker]) -> String {
+    markers.iter().map(|marker| marker.plain_text()).collect()
+}
+
+/// Helper to call `visit` on a list of markers and everything nested in them
+fn walk_markers(markers: &[Marker], visit: &mut impl FnMut(&Marker)) {
+    for marker in markers {
+        visit(marker);
+        match marker {
+            Marker::Placeholder { children, .. } => walk_markers(children, visit),
+            Marker::Variable {
+                default: Some(children),
+                ..
+            } => walk_markers(children, visit),
+            _ => {}
+        }
+    }
+}
+
+/// Helper to convert a list of markers to the snippet syntax
+fn display_markers(markers: &[Marker], specials: &[char]) -> String {
+    markers
+        .iter()
+        .map(|marker| marker.display(specials))
+        .collect()
+}
+
+/// Helper to escape the special characters of text with a backslash
+///
+/// A backslash is only escaped when it would otherwise escape the character after it.
+fn escape(text: &str, specials: &[char]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut escaped = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        let next = chars.get(index + 1);
+        let escapes_next = next.is_none_or(|next| *next == '\\' || specials.contains(next));
+        if specials.contains(c) || (*c == '\\' && escapes_next) {
+            escaped.push('\\');
+        }
+        escaped.push(*c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ast_display() {
+        let ast = SnippetAst {
+            markers: vec![
+                Marker::Text("cost: $".to_string()),
+                Marker::Placeholder {
+                    index: 1,
+                    children: vec![
+                        Marker::Text("{a}".to_string()),
+                        Marker::Tabstop {
+                            index: 2,
+                            transform: None,
+                        },
+                    ],
+                },
+                Marker::Choice {
+                    index: 3,
+                    options: vec!["a,b".to_string(), "c".to_string()],
+                },
+                Marker::Variable {
+                    name: "TM_FILENAME".to_string(),
+                    default: None,
+                    transform: Some(Transform {
+                        regex: "(.*)".to_string(),
+                        format: vec![FormatItem::Modifier {
+                            group: 1,
+                            modifier: "upcase".to_string(),
+                        }],
+                        options: String::new(),
+                    }),
+                },
+            ],
+        };
+
+        assert_eq!(
+            ast.display(),
+            "cost: \\$${1:{a\\}$2}${3|a\\,b,c|}${TM_FILENAME/(.*)/${1:/upcase}/}"
+        );
+        assert_eq!(ast.tabstops(), vec![1, 2, 3]);
+        assert_eq!(plain_text(&ast.markers), "cost: ${a}a,b");
+    }
+}
@@ -0,0 +1,161 @@
+//! Structures to model the order snippet names are sorted in
+
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// The ways of comparing snippet names when sorting
+///
+/// Every collation falls back on the byte order of the names, so names that compare equal
+/// (e.g. `Print` and `print` ignoring case) still have a stable order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Collation {
+    /// Ignore case (`alpha` < `Beta` < `gamma`)
+    #[default]
+    CaseInsensitive,
+    /// Byte order, so uppercase comes first (`Beta` < `alpha` < `gamma`)
+    CaseSensitive,
+    /// Ignore case and compare the runs of digits as numbers (`snip2` < `snip10`)
+    Natural,
+    /// Ignore case and accents (`eclair` < `Éclair` < `fig`)
+    Unicode,
+}
+
+impl Collation {
+    /// Compares two snippet names
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let ordering = match self {
+            Collation::CaseInsensitive => a.to_lowercase().cmp(&b.to_lowercase()),
+            Collation::CaseSensitive => Ordering::Equal,
+            Collation::Natural => natural_compare(a, b),
+            Collation::Unicode => unicode_key(a).cmp(&unicode_key(b)),
+        };
+        ordering.then_with(|| a.cmp(b))
+    }
+}
+
+/// Helper to compare names chunk by chunk, where the runs of digits are compared as numbers
+fn natural_compare(a: &str, b: &str) -> Ordering {
+    let (a_chunks, b_chunks) = (chunks(a), chunks(b));
+    for (a_chunk, b_chunk) in a_chunks.iter().zip(b_chunks.iter()) {
+        let ordering = match (a_chunk, b_chunk) {
+            (Chunk::Number(a_digits), Chunk::Number(b_digits)) => {
+                let (a_digits, b_digits) = (
+                    a_digits.trim_start_matches('0'),
+                    b_digits.trim_start_matches('0'),
+                );
+                a_digits
+                    .len()
+                    .cmp(&b_digits.len())
+                    .then_with(|| a_digits.cmp(b_digits))
+            }
+            (Chunk::Text(a_text), Chunk::Text(b_text)) => {
+                a_text.to_lowercase().cmp(&b_text.to_lowercase())
+            }
+            // Numbers sort before text, like the digits sort before the letters
+            (Chunk::Number(_), Chunk::Text(_)) => Ordering::Less,
+            (Chunk::Text(_), Chunk::Number(_)) => Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a_chunks.len().cmp(&b_chunks.len())
+}
+
+/// A run of digits or of anything else in a name
+enum Chunk<'a> {
+    Number(&'a str),
+    Text(&'a str),
+}
+
+/// Helper to split a name into its runs of digits and of anything else
+fn chunks(name: &str) -> Vec<Chunk<'_>> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    let mut start = 0;
+    let mut in_number = false;
+    for (index, c) in name.char_indices() {
+        if c.is_ascii_digit() != in_number {
+            if index > start {
+                chunks.push(match in_number {
+                    true => Chunk::Number(&name[start..index]),
+                    false => Chunk::Text(&name[start..index]),
+                });
+            }
+            start = index;
+            in_number = c.is_ascii_digit();
+        }
+    }
+    if start < name.len() {
+        chunks.push(match in_number {
+            true => Chunk::Number(&name[start..]),
+            false => Chunk::Text(&name[start..]),
+        });
+    }
+    chunks
+}
+
+/// Helper to get the name without case or accents (`Éclair` -> `eclair`)
+fn unicode_key(name: &str) -> String {
+    name.nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collation: Collation, names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        names.sort_by(|a, b| collation.compare(a, b));
+        names
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        assert_eq!(
+            sorted(
+                Collation::CaseInsensitive,
+                &["Zeta", "alpha", "beta", "Alpha"]
+            ),
+            vec!["Alpha", "alpha", "beta", "Zeta"]
+        );
+    }
+
+    #[test]
+    fn test_case_sensitive() {
+        assert_eq!(
+            sorted(Collation::CaseSensitive, &["alpha", "Zeta", "beta"]),
+            vec!["Zeta", "alpha", "beta"]
+        );
+    }
+
+    #[test]
+    fn test_natural() {
+        assert_eq!(
+            sorted(
+                Collation::Natural,
+                &["snip10", "Snip2", "snip1", "snip", "snip02b", "snip2a"]
+            ),
+            vec!["snip", "snip1", "Snip2", "snip2a", "snip02b", "snip10"]
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(
+            sorted(
+                Collation::Unicode,
+                &["fig", "Éclair", "eclair", "Ölfass", "apple"]
+            ),
+            vec!["apple", "eclair", "Éclair", "fig", "Ölfass"]
+        );
+        // Without the Unicode collation, accented letters sort after every ASCII letter
+        assert_eq!(
+            sorted(Collation::CaseInsensitive, &["fig", "éclair", "zed"]),
+            vec!["fig", "zed", "éclair"]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+//! Structures to model the snippet formats that tekton reads and writes
+
+use clap::ValueEnum;
+
+/// The snippet formats, as given to `--from` and `--to`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// VS Code snippets (`*.json` or `*.code-snippets`)
+    #[value(alias = "code-snippets", alias = "vscode")]
+    Json,
+    /// Snipmate snippets (`*.snippet`)
+    #[value(alias = "snippet")]
+    Snipmate,
+    /// UltiSnips snippets (`*.snippets`)
+    #[value(alias = "snippets")]
+    Ultisnips,
+    /// LuaSnip snippets (`*.lua`), only written
+    #[value(alias = "lua")]
+    Luasnip,
+    /// Sublime Text snippets (`*.sublime-snippet`, or a directory of them)
+    #[value(alias = "sublime-snippet")]
+    Sublime,
+    /// yasnippet snippets (a `<language>-mode/` directory)
+    Yasnippet,
+    /// JetBrains live templates (`*.xml`), only written
+    #[value(alias = "xml")]
+    Jetbrains,
+}
+
+impl Format {
+    /// The name of the format in the composer mappings, which is the extension of its files
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Snipmate => "snippet",
+            Format::Ultisnips => "snippets",
+            Format::Luasnip => "lua",
+            Format::Sublime => "sublime-snippet",
+            Format::Yasnippet => "yasnippet",
+            Format::Jetbrains => "xml",
+        }
+    }
+
+    /// The format of a file extension (or of a directory type from `get_dirtype`)
+    ///
+    /// `*.snippets` is read as UltiSnips here, though Snipmate files use it too (see `Format::sniff`).
+    pub fn from_tag(tag: &str) -> Option<Format> {
+        match tag {
+            "json" | "code-snippets" => Some(Format::Json),
+            "snippet" => Some(Format::Snipmate),
+            "snippets" => Some(Format::Ultisnips),
+            "lua" => Some(Format::Luasnip),
+            "sublime-snippet" => Some(Format::Sublime),
+            "yasnippet" => Some(Format::Yasnippet),
+            "xml" => Some(Format::Jetbrains),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a snippet file from its contents
+    ///
+    /// A JSON object (or JSONC comment) is JSON, an `endsnippet` line is UltiSnips and
+    /// `snippet` headers without any `endsnippet` are Snipmate.
+    pub fn sniff(contents: &str) -> Option<Format> {
+        let start = contents.trim_start();
+        let is_line = |text: &str| contents.lines().any(|line| line.trim_end() == text);
+        let has_header = contents.lines().any(|line| line.starts_with("snippet "));
+
+        if start.starts_with('{') || start.starts_with("//") || start.starts_with("/*") {
+            Some(Format::Json)
+        } else if start.contains("<templateSet") {
+            Some(Format::Jetbrains)
+        } else if start.contains("<snippet>") {
+            Some(Format::Sublime)
+        } else if is_line("endsnippet") {
+            Some(Format::Ultisnips)
+        } else if has_header {
+            Some(Format::Snipmate)
+        } else if start.contains("require(\"luasnip\")") {
+            Some(Format::Luasnip)
+        } else {
+            None
+        }
+    }
+
+    /// The names of the formats as given to `--from` and `--to`, for the error messages
+    pub fn names() -> String {
+        Format::value_variants()
+            .iter()
+            .filter_map(|format| format.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        let ultisnips = "priority -50\n\nsnippet fn \"A function\"\nfn $1() {}\nendsnippet\n";
+        let snipmate = "# Comment\nsnippet fn A function\n\tfn ${1}() {}\n";
+
+        assert_eq!(
+            Format::sniff("  // comment\n{\"a\": {}}"),
+            Some(Format::Json)
+        );
+        assert_eq!(Format::sniff(ultisnips), Some(Format::Ultisnips));
+        assert_eq!(Format::sniff(snipmate), Some(Format::Snipmate));
+        assert_eq!(
+            Format::sniff("<snippet>\n\t<content>a</content>\n</snippet>"),
+            Some(Format::Sublime)
+        );
+        assert_eq!(Format::sniff("just some text"), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            Format::names(),
+            "json, snipmate, ultisnips, luasnip, sublime, yasnippet, jetbrains"
+        );
+    }
+}
@@ -39,6 +39,35 @@ pub struct FriendlySnippetBody {
     /// An optional description explaining the snippet
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// An optional, comma separated, list of languages the snippet applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// An optional flag marking the snippet as a template for new files
+    #[serde(
+        rename = "isFileTemplate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub is_file_template: Option<bool>,
+    /// Optional glob pattern(s) of the files the snippet is offered in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Globs>,
+    /// Optional glob pattern(s) of the files the snippet is never offered in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Globs>,
+    /// Any other fields of the snippet (e.g. `tags` or `author`), kept as-is so sorting doesn't lose them
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The glob pattern(s) of the `include` and `exclude` fields, which may be a string or a list
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[serde(untagged)]
+pub enum Globs {
+    /// A single glob pattern
+    One(String),
+    /// A list of glob patterns
+    Many(Vec<String>),
 }
 
 impl FriendlySnippetBody {
@@ -51,11 +80,38 @@ impl FriendlySnippetBody {
             prefix,
             body,
             description,
+            scope: None,
+            is_file_template: None,
+            include: None,
+            exclude: None,
+            extra: serde_json::Map::new(),
         }
     }
 }
 
+/// Helper to move an extra field into a typed field, leaving it in place if it has the wrong type
+///
+/// Arguments:
+/// - `extra`: the fields of a snippet that aren't modeled yet
+/// - `key`: the name of the field
+/// - `convert`: the conversion of the value to the typed field, `None` if it has the wrong type
+///
+/// Returns:
+/// - The typed value, or `None` when the field is missing or kept in `extra`
+pub fn take_extra<T>(
+    extra: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+    convert: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Option<T> {
+    let value = extra.get(key).and_then(convert);
+    if value.is_some() {
+        extra.retain(|name, _| name != key);
+    }
+    value
+}
+
 #[cfg(test)]
+#[allow(clippy::unnecessary_to_owned)]
 mod tests {
     use super::*;
     #[test]
@@ -104,4 +160,47 @@ mod tests {
             &expected_body
         );
     }
+
+    #[test]
+    fn extra_fields_are_kept() {
+        let input = r#"{"test":{"prefix":"snip","body":["x"],"author":"me","tags":["a","b"]}}"#;
+
+        let hp: FriendlySnippets = serde_json::from_str(input).unwrap();
+        let body = hp.snippets.get("test").unwrap();
+
+        assert_eq!(body.extra.len(), 2);
+        assert_eq!(body.extra["author"], "me");
+        assert_eq!(
+            serde_json::to_string(&hp).unwrap(),
+            r#"{"test":{"prefix":"snip","body":["x"],"author":"me","tags":["a","b"]}}"#
+        );
+    }
+
+    #[test]
+    fn code_snippets_fields() {
+        let input = r#"{
+            "header": {
+                "scope": "javascript,typescript",
+                "prefix": "header",
+                "body": ["// ${TM_FILENAME}"],
+                "isFileTemplate": true,
+                "include": "**/*.test.js",
+                "exclude": ["**/node_modules/**", "dist/**"]
+            }
+        }"#;
+
+        let hp: FriendlySnippets = serde_json::from_str(input).unwrap();
+        let body = hp.snippets.get("header").unwrap();
+
+        assert_eq!(body.scope, Some("javascript,typescript".to_string()));
+        assert_eq!(body.is_file_template, Some(true));
+        assert_eq!(body.include, Some(Globs::One("**/*.test.js".to_string())));
+        assert_eq!(
+            body.exclude,
+            Some(Globs::Many(vec![
+                "**/node_modules/**".to_string(),
+                "dist/**".to_string()
+            ]))
+        );
+    }
 }
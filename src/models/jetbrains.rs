@@ -0,0 +1,123 @@
+//! Structures to model the JetBrains Live Templates (`templateSet`) format
+
+use crate::utils::xml_escape;
+
+/// A structure representing a `<variable>` of a live template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVariable {
+    /// The name used between the dollar signs in the template text
+    pub name: String,
+    /// The expression computing the value (e.g. `fileName()`)
+    pub expression: String,
+    /// The expression used when `expression` gives no value (e.g. `"default"`)
+    pub default_value: String,
+    /// If the cursor should stop at the variable while expanding
+    pub always_stop_at: bool,
+}
+
+impl TemplateVariable {
+    /// A function to create new TemplateVariable structs
+    pub fn new(
+        name: String,
+        expression: String,
+        default_value: String,
+        always_stop_at: bool,
+    ) -> TemplateVariable {
+        TemplateVariable {
+            name,
+            expression,
+            default_value,
+            always_stop_at,
+        }
+    }
+
+    /// Converts the variable to its XML element
+    pub fn display(&self) -> String {
+        format!(
+            "    <variable name=\"{}\" expression=\"{}\" defaultValue=\"{}\" alwaysStopAt=\"{}\" />\n",
+            xml_escape(&self.name),
+            xml_escape(&self.expression),
+            xml_escape(&self.default_value),
+            self.always_stop_at
+        )
+    }
+}
+
+/// A structure representing a single `<template>` of a JetBrains `templateSet`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveTemplate {
+    /// The abbreviation that triggers the template
+    pub name: String,
+    /// The template text, using `$NAME$` variables
+    pub value: String,
+    /// A small summary of the template
+    pub description: Option<String>,
+    /// The variables used in `value`, in the order the cursor visits them
+    pub variables: Vec<TemplateVariable>,
+}
+
+impl LiveTemplate {
+    /// A function to create new LiveTemplate structs
+    pub fn new(
+        name: String,
+        value: String,
+        description: Option<String>,
+        variables: Vec<TemplateVariable>,
+    ) -> LiveTemplate {
+        LiveTemplate {
+            name,
+            value,
+            description,
+            variables,
+        }
+    }
+
+    /// Converts the template to its XML element
+    pub fn display(self) -> String {
+        let value = xml_escape(&self.value)
+            .replace('\n', "&#10;")
+            .replace('\t', "&#9;");
+        let description = xml_escape(self.description.as_deref().unwrap_or(""));
+
+        let mut template_string = format!(
+            "  <template name=\"{}\" value=\"{}\" description=\"{}\" toReformat=\"false\" toShortenFQNames=\"true\">\n",
+            xml_escape(&self.name),
+            value,
+            description
+        );
+        for variable in self.variables.iter() {
+            template_string += &variable.display();
+        }
+        template_string +=
+            "    <context>\n      <option name=\"OTHER\" value=\"true\" />\n    </context>\n";
+        template_string += "  </template>\n";
+        template_string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_template_display() {
+        let template = LiveTemplate::new(
+            String::from("pr"),
+            String::from("print(\"$VAR1$\");\n$END$"),
+            Some(String::from("Print & flush")),
+            vec![TemplateVariable::new(
+                String::from("VAR1"),
+                String::new(),
+                String::from("\"text\""),
+                true,
+            )],
+        );
+
+        assert_eq!(
+            template.display(),
+            String::from(
+                "  <template name=\"pr\" value=\"print(&quot;$VAR1$&quot;);&#10;$END$\" description=\"Print &amp; flush\" toReformat=\"false\" toShortenFQNames=\"true\">\n    <variable name=\"VAR1\" expression=\"\" defaultValue=\"&quot;text&quot;\" alwaysStopAt=\"true\" />\n    <context>\n      <option name=\"OTHER\" value=\"true\" />\n    </context>\n  </template>\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Structures to model the comments of JSON with comments (JSONC) snippet files
+
+use std::collections::HashMap;
+
+/// The comments of a JSONC snippet file, grouped by the part of the file they belong to
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct JsoncComments {
+    /// The comments before the first snippet
+    pub header: Vec<String>,
+    /// The comments before, after or inside of each snippet, keyed by the snippet name
+    pub entries: HashMap<String, Vec<String>>,
+    /// The comments after the last snippet
+    pub footer: Vec<String>,
+}
+
+impl JsoncComments {
+    /// If the file had no comments at all
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.footer.is_empty()
+            && self.entries.values().all(|comments| comments.is_empty())
+    }
+}
@@ -0,0 +1,81 @@
+//! Structures to model the problems found by `tekton lint`
+
+/// The rules checked by the linter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintRule {
+    /// Two snippets of the file share a prefix
+    DuplicatePrefix,
+    /// The body has no content
+    EmptyBody,
+    /// The snippet has no description
+    MissingDescription,
+    /// A `${` is never closed
+    UnbalancedBraces,
+    /// The tabstops skip a number (e.g. `$1` then `$3`)
+    TabstopGap,
+    /// A choice (`${1|a,b|}`) is malformed
+    InvalidChoice,
+    /// The body lines are indented with both tabs and spaces
+    MixedIndentation,
+}
+
+impl LintRule {
+    /// The name of the rule as shown in the diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::DuplicatePrefix => "duplicate-prefix",
+            LintRule::EmptyBody => "empty-body",
+            LintRule::MissingDescription => "missing-description",
+            LintRule::UnbalancedBraces => "unbalanced-braces",
+            LintRule::TabstopGap => "tabstop-gap",
+            LintRule::InvalidChoice => "invalid-choice",
+            LintRule::MixedIndentation => "mixed-indentation",
+        }
+    }
+}
+
+/// A single problem with a snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The name of the snippet (the prefix for Snipmate snippets)
+    pub snippet: String,
+    /// The rule that was broken
+    pub rule: LintRule,
+    /// What is wrong, and where in the body
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A function to create new Diagnostic structs
+    pub fn new(snippet: &str, rule: LintRule, message: String) -> Diagnostic {
+        Diagnostic {
+            snippet: snippet.to_string(),
+            rule,
+            message,
+        }
+    }
+
+    /// Converts the diagnostic to a line of the report
+    pub fn display(&self) -> String {
+        format!("`{}` [{}] {}", self.snippet, self.rule.name(), self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::new(
+            "print",
+            LintRule::TabstopGap,
+            "tabstop $2 is missing".to_string(),
+        );
+
+        assert_eq!(
+            diagnostic.display(),
+            "`print` [tabstop-gap] tabstop $2 is missing"
+        );
+    }
+}
@@ -1,5 +1,17 @@
-//! The structs to model FriendlySnippets or Snipmate snippets
+//! The structs to model snippet bodies, FriendlySnippets (and their JSONC comments), Snipmate, UltiSnips, Sublime Text, yasnippet or JetBrains snippets
 pub mod args;
+pub mod ast;
+pub mod collation;
+pub mod format;
 pub mod friendly;
+pub mod jetbrains;
+pub mod jsonc;
+pub mod lint;
 pub mod multiprefix_friendly;
+pub mod naming;
+pub mod repair;
 pub mod snipmate;
+pub mod sort;
+pub mod sublime;
+pub mod ultisnips;
+pub mod yasnippet;
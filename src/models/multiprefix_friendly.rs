@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use super::friendly::{take_extra, FriendlySnippets, Globs};
+
 /// The Struct representing the JSON file of snippets
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MultiPrefixTable {
@@ -22,6 +24,31 @@ pub struct MultiBody {
     /// An optional description explaining the snippet
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// An optional, comma separated, list of languages the snippet applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// An optional flag marking the snippet as a template for new files
+    #[serde(
+        rename = "isFileTemplate",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub is_file_template: Option<bool>,
+    /// Optional glob pattern(s) of the files the snippet is offered in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub include: Option<Globs>,
+    /// Optional glob pattern(s) of the files the snippet is never offered in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude: Option<Globs>,
+    /// An optional flag (used by LuaSnip) to only expand the trigger at word boundaries
+    #[serde(rename = "wordTrig", default, skip_serializing_if = "Option::is_none")]
+    pub word_trig: Option<bool>,
+    /// An optional priority (used by LuaSnip) when several snippets share a trigger
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+    /// Any other fields of the snippet, kept as-is so sorting doesn't lose them
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 impl MultiBody {
@@ -31,6 +58,82 @@ impl MultiBody {
             prefix,
             body,
             description: Some(description),
+            scope: None,
+            is_file_template: None,
+            include: None,
+            exclude: None,
+            word_trig: None,
+            priority: None,
+            extra: serde_json::Map::new(),
         }
     }
 }
+
+impl From<FriendlySnippets> for MultiPrefixTable {
+    fn from(friendlies: FriendlySnippets) -> Self {
+        let snippets = friendlies
+            .snippets
+            .into_iter()
+            .map(|(name, mut snippet)| {
+                // The LuaSnip fields are unknown to `FriendlySnippetBody`, so they live in its extra fields
+                let word_trig = take_extra(&mut snippet.extra, "wordTrig", |v| v.as_bool());
+                let priority = take_extra(&mut snippet.extra, "priority", |v| v.as_i64());
+                let body = MultiBody {
+                    prefix: snippet.prefix.into_iter().collect(),
+                    body: snippet.body,
+                    description: snippet.description,
+                    scope: snippet.scope,
+                    is_file_template: snippet.is_file_template,
+                    include: snippet.include,
+                    exclude: snippet.exclude,
+                    word_trig,
+                    priority,
+                    extra: snippet.extra,
+                };
+                (name, body)
+            })
+            .collect();
+        Self { snippets }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::friendly::FriendlySnippetBody;
+
+    #[test]
+    fn test_multiprefix_from_friendly() {
+        let mut friendlies = FriendlySnippets::new();
+        friendlies.snippets.insert(
+            "test".to_string(),
+            FriendlySnippetBody::new(Some("snip".to_string()), Vec::new(), None),
+        );
+
+        let table = MultiPrefixTable::from(friendlies);
+        let body = table.snippets.get("test").unwrap();
+
+        assert_eq!(body.prefix, vec!["snip".to_string()]);
+        assert_eq!(body.description, None);
+    }
+
+    #[test]
+    fn test_multiprefix_from_friendly_luasnip_fields() {
+        let mut friendlies = FriendlySnippets::new();
+        let mut snippet = FriendlySnippetBody::new(Some("snip".to_string()), Vec::new(), None);
+        snippet
+            .extra
+            .insert("priority".to_string(), serde_json::json!(2000));
+        snippet
+            .extra
+            .insert("author".to_string(), serde_json::json!("me"));
+        friendlies.snippets.insert("test".to_string(), snippet);
+
+        let table = MultiPrefixTable::from(friendlies);
+        let body = table.snippets.get("test").unwrap();
+
+        assert_eq!(body.priority, Some(2000));
+        assert_eq!(body.extra.len(), 1);
+        assert_eq!(body.extra["author"], "me");
+    }
+}
@@ -0,0 +1,142 @@
+//! Structures to model how the snippets of the formats without names (Snipmate, UltiSnips) are named in JSON
+
+use clap::ValueEnum;
+use std::collections::HashMap;
+
+/// Where the name of a converted snippet comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NameSource {
+    /// The trigger of the snippet
+    #[default]
+    Prefix,
+    /// The description of the snippet, or its prefix when it has none
+    Description,
+    /// Prompt for the name of every snippet
+    Interactive,
+}
+
+/// How to name the snippets converted to JSON
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnippetNaming {
+    /// Where the names come from when there is no template
+    pub source: NameSource,
+    /// A name made of `{prefix}` and `{description}` (e.g. `{prefix} - {description}`)
+    pub template: Option<String>,
+    /// Names given per prefix, which win over the source and the template
+    pub mapping: HashMap<String, String>,
+}
+
+impl SnippetNaming {
+    /// The name of a snippet
+    ///
+    /// A template using `{description}` falls back on the prefix for the snippets without a description.
+    ///
+    /// Arguments:
+    /// - `prefix`: the trigger of the snippet
+    /// - `description`: the description of the snippet, if it has one
+    ///
+    /// Returns:
+    /// - The name, or `None` when it is to be prompted for
+    pub fn name(&self, prefix: &str, description: Option<&str>) -> Option<String> {
+        if let Some(name) = self.mapping.get(prefix) {
+            return Some(name.clone());
+        }
+        let description = description.filter(|d| !d.trim().is_empty());
+        match (&self.template, description) {
+            (Some(template), None) if template.contains("{description}") => {
+                Some(prefix.to_string())
+            }
+            (Some(template), _) => Some(
+                template
+                    .replace("{prefix}", prefix)
+                    .replace("{description}", description.unwrap_or_default()),
+            ),
+            (None, _) => match self.source {
+                NameSource::Prefix => Some(prefix.to_string()),
+                NameSource::Description => Some(description.unwrap_or(prefix).to_string()),
+                NameSource::Interactive => None,
+            },
+        }
+    }
+
+    /// Whether the names that aren't mapped are prompted for
+    pub fn is_interactive(&self) -> bool {
+        self.template.is_none() && self.source == NameSource::Interactive
+    }
+}
+
+/// Makes a name unique by adding a number to it (`for`, `for (2)`, `for (3)`...)
+///
+/// Arguments:
+/// - `name`: the name wanted for the snippet
+/// - `is_taken`: whether a name is already used by another snippet
+///
+/// Returns:
+/// - The name itself when it is free, or else the first numbered name that is
+pub fn unique_name(name: String, is_taken: impl Fn(&str) -> bool) -> String {
+    if !is_taken(&name) {
+        return name;
+    }
+    (2..)
+        .map(|number| format!("{} ({})", name, number))
+        .find(|candidate| !is_taken(candidate))
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_sources() {
+        let by_description = SnippetNaming {
+            source: NameSource::Description,
+            ..SnippetNaming::default()
+        };
+        let interactive = SnippetNaming {
+            source: NameSource::Interactive,
+            ..SnippetNaming::default()
+        };
+
+        assert_eq!(
+            SnippetNaming::default().name("for", Some("A loop")),
+            Some("for".to_string())
+        );
+        assert_eq!(
+            by_description.name("for", Some("A loop")),
+            Some("A loop".to_string())
+        );
+        assert_eq!(by_description.name("for", None), Some("for".to_string()));
+        assert_eq!(interactive.name("for", Some("A loop")), None);
+    }
+
+    #[test]
+    fn test_template_and_mapping() {
+        let naming = SnippetNaming {
+            template: Some("{prefix} - {description}".to_string()),
+            mapping: HashMap::from([("fn".to_string(), "Function".to_string())]),
+            ..SnippetNaming::default()
+        };
+
+        assert_eq!(
+            naming.name("for", Some("A loop")),
+            Some("for - A loop".to_string())
+        );
+        assert_eq!(naming.name("for", Some(" ")), Some("for".to_string()));
+        assert_eq!(naming.name("fn", None), Some("Function".to_string()));
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken = ["for", "for (2)"];
+
+        assert_eq!(
+            unique_name("for".to_string(), |name| taken.contains(&name)),
+            "for (3)"
+        );
+        assert_eq!(
+            unique_name("while".to_string(), |name| taken.contains(&name)),
+            "while"
+        );
+    }
+}
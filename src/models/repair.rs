@@ -0,0 +1,171 @@
+//! Structures to model how the JSON snippets missing a prefix are repaired, by prompts or an answers file
+
+use crate::errors::TektonError;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+
+/// The document a snippet is opened as in `$EDITOR`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EditFormat {
+    /// A JSON object, with the body as a list of lines
+    #[default]
+    Json,
+    /// A TOML table, with the body as a multi-line string
+    Toml,
+}
+
+impl EditFormat {
+    /// The extension of the temporary file, so the editor highlights it
+    pub fn extension(&self) -> &'static str {
+        match self {
+            EditFormat::Json => "json",
+            EditFormat::Toml => "toml",
+        }
+    }
+}
+
+/// How to repair the JSON snippets that are missing a prefix
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrefixRepair {
+    /// Prompt for the prefixes that aren't answered
+    pub interactive: bool,
+    /// The prefixes to give the snippets, by snippet name (from `--answers`)
+    pub answers: HashMap<String, String>,
+    /// Open the snippets in `$EDITOR` as this document instead of prompting for a prefix (from `--editor`)
+    pub editor: Option<EditFormat>,
+    /// The editor to run instead of `$VISUAL` or `$EDITOR` (e.g. `code --wait`)
+    pub editor_command: Option<String>,
+}
+
+impl PrefixRepair {
+    /// The answered prefix of a snippet, an empty answer is no answer
+    pub fn answer(&self, name: &str) -> Option<&str> {
+        self.answers
+            .get(name)
+            .map(|prefix| prefix.as_str())
+            .filter(|prefix| !prefix.trim().is_empty())
+    }
+
+    /// The same answers without prompting, for where there is no one to prompt (e.g. stdin or `--check`)
+    pub fn answers_only(&self) -> PrefixRepair {
+        PrefixRepair {
+            interactive: false,
+            answers: self.answers.clone(),
+            editor: None,
+            editor_command: None,
+        }
+    }
+
+    /// Reads the contents of an answers file, a table of snippet names and prefixes
+    ///
+    /// Arguments:
+    /// - `fname`: the name of the answers file, `*.toml` files are read as TOML and the others as JSON
+    /// - `contents`: the contents of the answers file
+    ///
+    /// Returns:
+    /// - The prefixes by snippet name, or an error if the file isn't a table of strings
+    pub fn parse_answers(
+        fname: &str,
+        contents: &str,
+    ) -> Result<HashMap<String, String>, TektonError> {
+        match is_toml(fname) {
+            true => toml::from_str(contents).map_err(|e| {
+                let line = e
+                    .span()
+                    .map_or(1, |span| contents[..span.start].matches('\n').count() + 1);
+                TektonError::Reason(format!(
+                    "Invalid TOML in `{}` at line {}: {}",
+                    fname,
+                    line,
+                    e.message()
+                ))
+            }),
+            false => {
+                serde_json::from_str(contents).map_err(|e| TektonError::from(e).in_file(fname))
+            }
+        }
+    }
+
+    /// Builds an answers file listing the snippets missing a prefix, to fill in and give to `--answers`
+    ///
+    /// Arguments:
+    /// - `fname`: the name of the answers file, `*.toml` files are written as TOML and the others as JSON
+    /// - `names`: the snippets missing a prefix
+    ///
+    /// Returns:
+    /// - The contents of the answers file, with the prefixes already answered filled in
+    pub fn answers_template(&self, fname: &str, names: &[String]) -> Result<String, TektonError> {
+        let template: BTreeMap<&str, &str> = names
+            .iter()
+            .map(|name| (name.as_str(), self.answer(name).unwrap_or_default()))
+            .collect();
+        match is_toml(fname) {
+            true => toml::to_string(&template)
+                .map(|table| {
+                    format!(
+                        "# The snippets missing a prefix, fill in their prefixes and give this file to `--answers`\n{}",
+                        table
+                    )
+                })
+                .map_err(|e| TektonError::Reason(e.to_string())),
+            false => Ok(serde_json::to_string_pretty(&template)? + "\n"),
+        }
+    }
+}
+
+/// Helper to tell the TOML answers files from the JSON ones
+fn is_toml(fname: &str) -> bool {
+    fname.to_lowercase().ends_with(".toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answers() -> Result<(), TektonError> {
+        let toml = "\"Print line\" = \"pl\"\nFormat = \"\"\n";
+        let json = r#"{"Print line": "pl", "Format": ""}"#;
+
+        for (fname, contents) in [("answers.toml", toml), ("answers.json", json)] {
+            let repair = PrefixRepair {
+                answers: PrefixRepair::parse_answers(fname, contents)?,
+                ..PrefixRepair::default()
+            };
+            assert_eq!(repair.answer("Print line"), Some("pl"));
+            assert_eq!(repair.answer("Format"), None);
+        }
+
+        assert!(
+            PrefixRepair::parse_answers("answers.toml", "a = \"x\"\nb = 1")
+                .unwrap_err()
+                .to_string()
+                .starts_with("Invalid TOML in `answers.toml` at line 2: ")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_answers_template() -> Result<(), TektonError> {
+        let repair = PrefixRepair {
+            answers: HashMap::from([("Print line".to_string(), "pl".to_string())]),
+            ..PrefixRepair::default()
+        };
+        let names = vec!["Print line".to_string(), "Format".to_string()];
+
+        let toml = repair.answers_template("answers.toml", &names)?;
+        assert_eq!(
+            toml.lines().skip(1).collect::<Vec<&str>>(),
+            vec!["Format = \"\"", "\"Print line\" = \"pl\""]
+        );
+        assert_eq!(
+            PrefixRepair::parse_answers("answers.toml", &toml)?.get("Print line"),
+            Some(&"pl".to_string())
+        );
+        assert_eq!(
+            repair.answers_template("answers.json", &names)?,
+            "{\n  \"Format\": \"\",\n  \"Print line\": \"pl\"\n}\n"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+//! Structures to model how the snippets of a file are ordered when sorting
+
+use super::{collation::Collation, friendly::FriendlySnippetBody, multiprefix_friendly::MultiBody};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// The part of a snippet that decides its place in the file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// The name of the snippet (its key in the JSON table)
+    #[default]
+    Name,
+    /// The trigger of the snippet (the first one when there are several)
+    Prefix,
+    /// The description of the snippet
+    Description,
+}
+
+/// The fields of a snippet body that it can be sorted or grouped by
+pub trait SortFields: Serialize {
+    /// The trigger of the snippet, the first one when there are several
+    fn first_prefix(&self) -> Option<&str>;
+
+    /// The description of the snippet
+    fn description(&self) -> Option<&str>;
+
+    /// The value of any (JSON) field of the snippet as text, e.g. `scope`
+    fn field(&self, name: &str) -> Option<String> {
+        match serde_json::to_value(self).ok()?.get(name)? {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+}
+
+impl SortFields for FriendlySnippetBody {
+    fn first_prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+impl SortFields for MultiBody {
+    fn first_prefix(&self) -> Option<&str> {
+        self.prefix.first().map(|prefix| prefix.as_str())
+    }
+
+    fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+}
+
+/// The order to write the snippets of a file in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortOrder {
+    /// The part of the snippets to sort by
+    pub key: SortKey,
+    /// How the keys (and group values) are compared
+    pub collation: Collation,
+    /// A field (e.g. `scope`) to group the snippets by before sorting them, the snippets without it come last
+    pub group_by: Option<String>,
+}
+
+impl SortOrder {
+    /// Sorts the snippets given as their name and body
+    ///
+    /// The group and key of each snippet are worked out once, not on every comparison (grouping by a
+    /// field serializes the snippet to find it).
+    ///
+    /// Arguments:
+    /// - `snippets`: the names and bodies of the snippets
+    ///
+    /// Returns:
+    /// - The snippets in order
+    pub fn sort<'n, 's, T: SortFields>(
+        &self,
+        snippets: Vec<(&'n str, &'s T)>,
+    ) -> Vec<(&'n str, &'s T)> {
+        let mut keyed: Vec<(Option<String>, String, (&str, &T))> = snippets
+            .into_iter()
+            .map(|snippet| (self.group(snippet.1), self.key(snippet), snippet))
+            .collect();
+        keyed.sort_by(|a, b| self.compare_keys((&a.0, &a.1, a.2 .0), (&b.0, &b.1, b.2 .0)));
+        keyed.into_iter().map(|(_, _, snippet)| snippet).collect()
+    }
+
+    /// Helper to get the value of the field a snippet is grouped by
+    fn group<T: SortFields>(&self, snippet: &T) -> Option<String> {
+        self.group_by
+            .as_deref()
+            .and_then(|field| snippet.field(field))
+    }
+
+    /// Helper to get the part of a snippet it is sorted by
+    fn key<T: SortFields>(&self, (name, snippet): (&str, &T)) -> String {
+        match self.key {
+            SortKey::Name => name.to_string(),
+            SortKey::Prefix => snippet.first_prefix().unwrap_or_default().to_string(),
+            SortKey::Description => snippet.description().unwrap_or_default().to_string(),
+        }
+    }
+
+    /// Helper to compare two snippets given as their group, key and name
+    fn compare_keys(
+        &self,
+        a: (&Option<String>, &String, &str),
+        b: (&Option<String>, &String, &str),
+    ) -> Ordering {
+        let by_group = match (a.0, b.0) {
+            (Some(a_group), Some(b_group)) => self.collation.compare(a_group, b_group),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+
+        by_group
+            .then_with(|| self.collation.compare(a.1, b.1))
+            .then_with(|| self.collation.compare(a.2, b.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(
+        prefix: &str,
+        description: Option<&str>,
+        scope: Option<&str>,
+    ) -> FriendlySnippetBody {
+        let mut body = FriendlySnippetBody::new(
+            Some(prefix.to_string()),
+            Vec::new(),
+            description.map(|d| d.to_string()),
+        );
+        body.scope = scope.map(|s| s.to_string());
+        body
+    }
+
+    fn sorted(
+        order: &SortOrder,
+        snippets: &[(&'static str, FriendlySnippetBody)],
+    ) -> Vec<&'static str> {
+        let snippets: Vec<(&str, &FriendlySnippetBody)> =
+            snippets.iter().map(|(name, body)| (*name, body)).collect();
+        order
+            .sort(snippets)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let snippets = [
+            ("Print line", snippet("pl", Some("A line"), None)),
+            ("Assert", snippet("zz", None, None)),
+            ("Format", snippet("fmt", Some("Builds a string"), None)),
+        ];
+
+        let by_prefix = SortOrder {
+            key: SortKey::Prefix,
+            ..SortOrder::default()
+        };
+        let by_description = SortOrder {
+            key: SortKey::Description,
+            ..SortOrder::default()
+        };
+
+        assert_eq!(
+            sorted(&SortOrder::default(), &snippets),
+            vec!["Assert", "Format", "Print line"]
+        );
+        assert_eq!(
+            sorted(&by_prefix, &snippets),
+            vec!["Format", "Print line", "Assert"]
+        );
+        assert_eq!(
+            sorted(&by_description, &snippets),
+            vec!["Assert", "Print line", "Format"]
+        );
+    }
+
+    #[test]
+    fn test_group_by_field() {
+        let snippets = [
+            ("b", snippet("z", None, Some("rust"))),
+            ("a", snippet("y", None, None)),
+            ("c", snippet("x", None, Some("python"))),
+            ("d", snippet("w", None, Some("rust"))),
+        ];
+
+        let order = SortOrder {
+            key: SortKey::Prefix,
+            group_by: Some("scope".to_string()),
+            ..SortOrder::default()
+        };
+
+        assert_eq!(sorted(&order, &snippets), vec!["c", "d", "b", "a"]);
+    }
+
+    #[test]
+    fn test_group_is_found_once_per_snippet() {
+        #[derive(Serialize)]
+        struct Counted {
+            #[serde(skip)]
+            lookups: std::cell::Cell<usize>,
+        }
+        impl SortFields for Counted {
+            fn first_prefix(&self) -> Option<&str> {
+                None
+            }
+            fn description(&self) -> Option<&str> {
+                None
+            }
+            fn field(&self, _: &str) -> Option<String> {
+                self.lookups.set(self.lookups.get() + 1);
+                None
+            }
+        }
+
+        let names: Vec<String> = (0..100).map(|index| format!("snip{}", index)).collect();
+        let bodies: Vec<Counted> = names
+            .iter()
+            .map(|_| Counted {
+                lookups: std::cell::Cell::new(0),
+            })
+            .collect();
+        let order = SortOrder {
+            group_by: Some("scope".to_string()),
+            ..SortOrder::default()
+        };
+
+        order.sort(
+            names
+                .iter()
+                .map(|name| name.as_str())
+                .zip(bodies.iter())
+                .collect(),
+        );
+        assert!(bodies.iter().all(|body| body.lookups.get() == 1));
+    }
+
+    #[test]
+    fn test_multi_body_first_prefix() {
+        let body = MultiBody::new(
+            vec!["one".to_string(), "two".to_string()],
+            Vec::new(),
+            String::new(),
+        );
+
+        assert_eq!(body.first_prefix(), Some("one"));
+        assert_eq!(body.field("prefix"), Some("[\"one\",\"two\"]".to_string()));
+    }
+}
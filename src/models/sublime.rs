@@ -0,0 +1,83 @@
+//! Structures to model the Sublime Text (`*.sublime-snippet`) snippet format
+
+use crate::utils::xml_escape;
+
+/// A structure representing a single Sublime Text / TextMate XML snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SublimeSnippet {
+    /// The content of the snippet
+    pub content: String,
+    /// The trigger for the snippet
+    pub tab_trigger: Option<String>,
+    /// The scope selector the snippet is active in (e.g. `source.rust`)
+    pub scope: Option<String>,
+    /// A small summary of the snippet
+    pub description: Option<String>,
+}
+
+impl SublimeSnippet {
+    /// A function to create new SublimeSnippet structs
+    pub fn new(
+        content: String,
+        tab_trigger: Option<String>,
+        scope: Option<String>,
+        description: Option<String>,
+    ) -> SublimeSnippet {
+        SublimeSnippet {
+            content,
+            tab_trigger,
+            scope,
+            description,
+        }
+    }
+
+    /// Converts the snippet to its XML string
+    pub fn display(self) -> String {
+        // `]]>` is the only sequence that can't live inside of a CDATA section
+        let content = self.content.replace("]]>", "]]]]><![CDATA[>");
+        let mut snippet_string =
+            "<snippet>\n\t<content><![CDATA[\n".to_string() + &content + "\n]]></content>\n";
+
+        if let Some(tab_trigger) = &self.tab_trigger {
+            snippet_string += &format!("\t<tabTrigger>{}</tabTrigger>\n", xml_escape(tab_trigger));
+        }
+        if let Some(scope) = &self.scope {
+            snippet_string += &format!("\t<scope>{}</scope>\n", xml_escape(scope));
+        }
+        if let Some(description) = &self.description {
+            snippet_string +=
+                &format!("\t<description>{}</description>\n", xml_escape(description));
+        }
+        snippet_string += "</snippet>\n";
+        snippet_string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SublimeSnippet;
+
+    #[test]
+    fn test_sublime_snippet_display() {
+        let snip = SublimeSnippet::new(
+            String::from("if (${1:a < b}) {\n\t$0\n}"),
+            Some(String::from("if")),
+            Some(String::from("source.rust")),
+            Some(String::from("if & else")),
+        );
+
+        assert_eq!(
+            snip.display(),
+            String::from(
+                "<snippet>\n\t<content><![CDATA[\nif (${1:a < b}) {\n\t$0\n}\n]]></content>\n\t<tabTrigger>if</tabTrigger>\n\t<scope>source.rust</scope>\n\t<description>if &amp; else</description>\n</snippet>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_sublime_snippet_display_escapes_cdata_end() {
+        let snip = SublimeSnippet::new(String::from("a]]>b"), None, None, None);
+
+        assert!(snip.display().contains("<![CDATA[\na]]]]><![CDATA[>b\n]]>"));
+    }
+}
@@ -0,0 +1,116 @@
+//! Structures to model the UltiSnips snippet format
+
+/// The option flags UltiSnips understands for a snippet header
+pub const ULTISNIPS_OPTIONS: &str = "bwirAetsm";
+
+/// A structure representing a single UltiSnips snippet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UltiSnip {
+    /// The trigger for the snippet
+    pub prefix: String,
+    /// The content of the snippet
+    pub body: Vec<String>,
+    /// A small summary of the snippet
+    pub description: Option<String>,
+    /// The option flags following the description (e.g. `bA`)
+    pub options: String,
+    /// The `priority` in effect when the snippet was declared
+    pub priority: Option<i64>,
+}
+
+impl UltiSnip {
+    /// A function to create new UltiSnip structs without any options or priority
+    pub fn new(prefix: String, body: Vec<String>, description: Option<String>) -> UltiSnip {
+        UltiSnip {
+            prefix,
+            body,
+            description,
+            options: String::new(),
+            priority: None,
+        }
+    }
+
+    /// Converts the snippet to a string
+    pub fn display(self) -> String {
+        let mut snippet_string = "snippet ".to_string() + &quote_trigger(&self.prefix);
+
+        if let Some(description) = &self.description {
+            if !description.is_empty() {
+                // UltiSnips ends the description at its last quote and has no escape for one
+                snippet_string = snippet_string + " \"" + &description.replace('"', "'") + "\"";
+            }
+        }
+
+        if !self.options.is_empty() {
+            // Options are only recognized after a description
+            if self.description.as_deref().unwrap_or("").is_empty() {
+                snippet_string += " \"\"";
+            }
+            snippet_string = snippet_string + " " + &self.options;
+        }
+        snippet_string += "\n";
+
+        for line in self.body {
+            snippet_string = snippet_string + &line + "\n";
+        }
+        snippet_string += "endsnippet\n";
+        snippet_string
+    }
+}
+
+/// A structure representing everything read from an UltiSnips file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UltiSnippets {
+    /// The snippets found in the file
+    pub snippets: Vec<UltiSnip>,
+    /// The filetypes pulled in by `extends` lines
+    pub extends: Vec<String>,
+    /// The number of `global` blocks found in the file
+    pub globals: usize,
+    /// The other directives (`clearsnippets`, `context`, ...) found in the file
+    pub directives: Vec<String>,
+}
+
+/// Helper to wrap a trigger in delimiters when it contains whitespace
+fn quote_trigger(trigger: &str) -> String {
+    if !trigger.contains(char::is_whitespace) {
+        return trigger.to_string();
+    }
+    let delimiter = ['"', '!', '|', '#', '%', '/']
+        .into_iter()
+        .find(|c| !trigger.contains(*c))
+        .unwrap_or('"');
+    format!("{}{}{}", delimiter, trigger, delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UltiSnip;
+
+    #[test]
+    fn test_ultisnip_display() {
+        let mut snip = UltiSnip::new(
+            String::from("test"),
+            vec![String::from("A line of snippet")],
+            Some(String::from("An epic test description")),
+        );
+        snip.options = String::from("b");
+
+        assert_eq!(
+            snip.display(),
+            String::from(
+                "snippet test \"An epic test description\" b\nA line of snippet\nendsnippet\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_ultisnip_display_multiword_trigger() {
+        let snip = UltiSnip::new(String::from("two words"), vec![String::from("x")], None);
+
+        assert_eq!(
+            snip.display(),
+            String::from("snippet \"two words\"\nx\nendsnippet\n")
+        );
+    }
+}
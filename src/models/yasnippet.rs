@@ -0,0 +1,61 @@
+//! Structures to model the Emacs yasnippet snippet format
+
+/// A structure representing a single yasnippet snippet file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YaSnippet {
+    /// The name shown in the yasnippet menus (`# name:`)
+    pub name: String,
+    /// The trigger for the snippet (`# key:`)
+    pub key: Option<String>,
+    /// The content of the snippet
+    pub body: Vec<String>,
+    /// Any other header directives (`# group:`, `# condition:`, ...) as name and value pairs
+    pub directives: Vec<(String, String)>,
+}
+
+impl YaSnippet {
+    /// A function to create new YaSnippet structs without any extra directives
+    pub fn new(name: String, key: Option<String>, body: Vec<String>) -> YaSnippet {
+        YaSnippet {
+            name,
+            key,
+            body,
+            directives: Vec::new(),
+        }
+    }
+
+    /// Converts the snippet to the contents of its file
+    pub fn display(self) -> String {
+        let mut snippet_string =
+            "# -*- mode: snippet -*-\n# name: ".to_string() + &self.name + "\n";
+
+        if let Some(key) = &self.key {
+            snippet_string = snippet_string + "# key: " + key + "\n";
+        }
+        for (directive, value) in self.directives.iter() {
+            snippet_string = snippet_string + "# " + directive + ": " + value + "\n";
+        }
+        snippet_string += "# --\n";
+        snippet_string += &self.body.join("\n");
+        snippet_string
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::YaSnippet;
+
+    #[test]
+    fn test_yasnippet_display() {
+        let snip = YaSnippet::new(
+            String::from("An epic test description"),
+            Some(String::from("test")),
+            vec![String::from("A line of"), String::from("  snippet")],
+        );
+
+        assert_eq!(
+            snip.display(),
+            String::from("# -*- mode: snippet -*-\n# name: An epic test description\n# key: test\n# --\nA line of\n  snippet")
+        );
+    }
+}
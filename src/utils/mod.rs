@@ -1,28 +1,64 @@
 //! Simple and easy to use utilities that may be used throughout the CLI program
 
-use core::panic;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{self, prelude::*, BufReader, Error};
+use std::fs::{self, File};
+use std::io::{self, prelude::*, Error};
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
 
-use serde::{Deserialize, Serialize};
-use walkdir::WalkDir;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{ser::SerializeMap, Serialize, Serializer};
 
 use crate::errors::TektonError;
+use crate::models::{
+    repair::PrefixRepair,
+    sort::{SortFields, SortOrder},
+};
+
+/// The file name that stands for stdin when reading, and stdout when writing
+pub const STDIO: &str = "-";
+
+/// The directories that never hold snippets, skipped when crawling
+const SKIPPED_DIRECTORIES: [&str; 2] = [".git", "node_modules"];
+
+/// The extensions of the snippet files that can be read, the only files kept when crawling
+const SNIPPET_EXTENSIONS: [&str; 5] = [
+    "json",
+    "code-snippets",
+    "snippet",
+    "snippets",
+    "sublime-snippet",
+];
+
+/// The JSON files found next to snippets (e.g. in a VS Code extension) that aren't snippets
+const NON_SNIPPET_FILES: [&str; 4] = [
+    "package.json",
+    "package-lock.json",
+    "tsconfig.json",
+    "jsconfig.json",
+];
 
 /// Function to retrive user input, looping until text is in the buffer
 /// and is not an empty line
 ///
+/// Exits the program when stdin is closed (or can't be read), since no answer will ever come.
+///
 /// Returns:
 /// - The user input as a String
+#[allow(clippy::needless_return)]
 pub fn get_input() -> String {
     let mut input = String::new();
     while input == String::new() {
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Error with reading input");
+        match io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => {
+                eprintln!("[Tekton Error]: No input left to answer the prompt with");
+                process::exit(1);
+            }
+            Ok(_) => {}
+        }
     }
     return input.trim().to_string();
 }
@@ -39,45 +75,201 @@ pub fn clear_terminal() {
 ///
 /// Returns:
 /// - Result of vector of String or Error
-pub fn read_lines(fname: &String) -> Result<Vec<String>, Error> {
-    let file = File::open(fname)?;
-    let buf = BufReader::new(file);
-    Ok(buf
+pub fn read_lines(fname: &str) -> Result<Vec<String>, Error> {
+    Ok(read_input(fname)?
         .lines()
-        .map(|line| line.expect("Could not parse line"))
+        .map(|line| line.to_string())
         .collect())
 }
+
+/// Function to read the whole of a file, where `-` reads stdin instead
+///
+/// Stdin is read once and kept, so reading `-` again (e.g. for the JSONC comments) gives the same text.
+///
+/// Arguments:
+/// - `fname` is the filename of the snippets to read from, or `-`
+///
+/// Returns:
+/// - Result of the contents as a String or Error
+pub fn read_input(fname: &str) -> Result<String, Error> {
+    static STDIN: OnceLock<String> = OnceLock::new();
+    if fname != STDIO {
+        return decode_utf8(fs::read(fname)?);
+    }
+    if let Some(input) = STDIN.get() {
+        return Ok(input.clone());
+    }
+    let mut input = Vec::new();
+    io::stdin().read_to_end(&mut input)?;
+    let input = decode_utf8(input)?;
+    Ok(STDIN.get_or_init(|| input).clone())
+}
+
+/// Helper to turn the bytes read into text, with the line of the first invalid UTF-8 byte in the error
+fn decode_utf8(bytes: Vec<u8>) -> Result<String, Error> {
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = e.utf8_error().valid_up_to();
+        let line = e.as_bytes()[..valid]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count()
+            + 1;
+        Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid UTF-8 on line {}", line),
+        )
+    })
+}
+
 /// Function to write to a newly created file.
 ///
+/// The snippets are written to a temporary file next to `output_name` which is then renamed over it,
+/// so an interrupted run never leaves a half-written file behind.
+///
 /// Arguments:
-/// - `output_name` : file name to write the snippets to
+/// - `output_name` : file name to write the snippets to, or `-` for stdout
 /// - `finished` : the final serialized string representation of the snippets
 ///
-pub fn write_to_file(output_name: String, finished: String) {
-    let mut outfile = File::create(Path::new("./").join(output_name))
-        .unwrap_or_else(|err| panic!("Could not create the file {}", err));
+/// Returns:
+/// - An ok result, or a TektonError with the file that could not be written
+pub fn write_to_file(output_name: String, finished: String) -> Result<(), TektonError> {
+    if output_name == STDIO {
+        return io::stdout()
+            .write_all(finished.as_bytes())
+            .map_err(|e| TektonError::from(e).in_file(STDIO));
+    }
+
+    let path = Path::new("./").join(&output_name);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, process::id()));
+    let written = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(finished.as_bytes())?;
+            // Keep the permissions of the file being replaced
+            if let Ok(metadata) = fs::metadata(&path) {
+                file.set_permissions(metadata.permissions())?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, &path));
+
+    written.map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        TektonError::from(e).in_file(&output_name)
+    })
+}
+
+/// Function to keep a copy of a file as `<name>.bak` before it is overwritten
+///
+/// Arguments:
+/// - `fname` : the file about to be overwritten, nothing is done if it doesn't exist yet
+///
+/// Returns:
+/// - An ok result, or a TektonError if the copy failed
+pub fn backup_file(fname: &str) -> Result<(), TektonError> {
+    let backup = format!("{}.bak", fname);
+    if fname == STDIO || !Path::new(fname).exists() {
+        return Ok(());
+    }
+    fs::copy(fname, &backup)
+        .map(|_| ())
+        .map_err(|e| TektonError::from(e).in_file(&backup))
+}
+
+/// Function to read an answers file (`--answers`), the prefixes to give the snippets missing one
+///
+/// Arguments:
+/// - `fname`: the TOML (`*.toml`) or JSON answers file
+///
+/// Returns:
+/// - The prefixes by snippet name, or a TektonError with the file that could not be read
+pub fn read_answers(fname: &str) -> Result<HashMap<String, String>, TektonError> {
+    let contents = read_input(fname).map_err(|e| TektonError::from(e).in_file(fname))?;
+    PrefixRepair::parse_answers(fname, &contents)
+}
+
+/// Function to write the answers file (`--emit-answers`) listing the snippets missing a prefix
+///
+/// Arguments:
+/// - `fname`: the TOML (`*.toml`) or JSON answers file to write, or `-` for stdout
+/// - `repair`: the answers already given, which are filled in
+/// - `names`: the snippets missing a prefix
+///
+/// Returns:
+/// - An ok result, or a TektonError with the file that could not be written
+pub fn write_answers(
+    fname: &str,
+    repair: &PrefixRepair,
+    names: &[String],
+) -> Result<(), TektonError> {
+    write_to_file(fname.to_string(), repair.answers_template(fname, names)?)?;
+    if fname != STDIO {
+        println!(
+            "[Tekton]: Listed {} snippet(s) missing a prefix in `{}`, fill it in and run again with `--answers {}`",
+            names.len(),
+            fname,
+            fname
+        );
+    }
+    Ok(())
+}
+
+/// Function to write a collection of snippet files into a directory, creating it if needed.
+///
+/// Arguments:
+/// - `output_dir` : the directory to write the snippets to
+/// - `files` : the file names and serialized string representations of the snippets
+///
+/// Returns:
+/// - An ok result, or a TektonError with the directory or file that could not be written
+pub fn write_to_dir(output_dir: String, files: Vec<(String, String)>) -> Result<(), TektonError> {
+    fs::create_dir_all(Path::new("./").join(&output_dir))
+        .map_err(|e| TektonError::from(e).in_file(&output_dir))?;
 
-    outfile
-        .write_all(finished.as_bytes())
-        .unwrap_or_else(|err| panic!("Could not write the snippets\n>>> Error >>>{}", err));
+    for (name, finished) in files {
+        let path = Path::new(&output_dir).join(name);
+        write_to_file(path.to_string_lossy().to_string(), finished)?;
+    }
+    Ok(())
 }
 
 /// Function to create a vector of PathBuf's that will be consumed
 /// by the program during runtime.
 ///
+/// The crawl honors the `.gitignore` and `.ignore` files, skips the `.git` and `node_modules`
+/// directories, the files without a snippet extension (e.g. `README.md`) and the JSON files that
+/// aren't snippets (e.g. `package.json`), and lists the files in name order.
+///
 /// Arguments:
 /// - `path`: the path to the directory or file to read
-/// - `crawl`: an optional boolean to indicate if the `path` is a directory crawl through.
+/// - `crawl`: a boolean to indicate if the `path` is a directory to crawl through.
 ///
 /// Returns:
 /// - A list of Pathbuf's representing files the program may read
-pub fn crawl_files(path: String, crawl: Option<String>) -> Vec<PathBuf> {
+pub fn crawl_files(path: String, crawl: bool) -> Vec<PathBuf> {
     let mut files: Vec<PathBuf> = Vec::new();
-    if crawl.is_some() {
-        // This for loop is from the WalkDir documentation, I claim no credit
-        // with respect to this.
-        for file in WalkDir::new(path).into_iter().filter_map(|file| file.ok()) {
-            if file.metadata().unwrap().is_file() {
+    if crawl {
+        let walker = WalkBuilder::new(path)
+            .hidden(false)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(|entry| {
+                entry.depth() == 0
+                    || !SKIPPED_DIRECTORIES
+                        .iter()
+                        .any(|dir| entry.file_name() == *dir)
+            })
+            .build();
+        for file in walker.filter_map(|file| file.ok()) {
+            let is_file = file.file_type().is_some_and(|filetype| filetype.is_file());
+            let name = file.file_name().to_string_lossy();
+            let is_snippet = get_filetype(&name)
+                .is_some_and(|filetype| SNIPPET_EXTENSIONS.contains(&filetype))
+                && !NON_SNIPPET_FILES.contains(&name.as_ref());
+            if is_file && is_snippet {
                 files.push(file.path().to_path_buf());
             }
         }
@@ -87,6 +279,25 @@ pub fn crawl_files(path: String, crawl: Option<String>) -> Vec<PathBuf> {
     files
 }
 
+/// Function to build a matcher for a list of glob patterns (e.g. `*.json` or `node_modules/**`)
+///
+/// Arguments:
+/// - `patterns`: the glob patterns from the cli
+///
+/// Returns:
+/// - The set of globs, or a TektonError naming the pattern that isn't a valid glob
+pub fn build_globset(patterns: &[String]) -> Result<GlobSet, TektonError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .map_err(|e| TektonError::Reason(format!("Invalid glob `{}`: {}", pattern, e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| TektonError::Reason(e.to_string()))
+}
+
 /// Helper function to get the file extension being passed in.
 ///
 /// Arguments:
@@ -98,50 +309,311 @@ pub fn get_filetype(filename: &str) -> Option<&str> {
     Path::new(filename).extension().and_then(OsStr::to_str)
 }
 
+/// Helper function to get the snippet format of a directory of snippets.
+///
+/// yasnippet directories are named after their major mode (e.g. `rust-mode/`), any other
+/// directory is treated as a collection of Sublime Text snippets. A path that doesn't exist yet
+/// (an output) is told apart by its name alone.
+///
+/// Arguments:
+/// - `dirname`: a string slice representing the name of the directory
+///
+/// Returns:
+/// - Optional string slice representing the format stored in the directory, `None` for a file
+pub fn get_dirtype(dirname: &str) -> Option<&str> {
+    let path = Path::new(dirname);
+    if path.exists() && !path.is_dir() {
+        None
+    } else if dirname.trim_end_matches('/').ends_with("-mode") {
+        Some("yasnippet")
+    } else if dirname.ends_with('/') || Path::new(dirname).is_dir() {
+        Some("sublime-snippet")
+    } else {
+        None
+    }
+}
+
+/// Helper function to turn a snippet name into a name that is safe to use as a file name.
+///
+/// Arguments:
+/// - `name`: the snippet name
+///
+/// Returns:
+/// - The name with any characters outside of letters, digits, spaces, `-`, `_` and `.` replaced by `_`
+pub fn sanitize_filename(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c,
+            ' ' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+
+    match sanitized.trim_start_matches('.') {
+        "" => "_".to_string(),
+        rest => rest.to_string(),
+    }
+}
+
+/// Helper function to end the sorted text with the same line break as the file it came from.
+///
+/// Arguments:
+/// - `sorted`: the sorted snippets, without a final line break
+/// - `original`: the contents of the file that was sorted
+///
+/// Returns:
+/// - The sorted text, ending with `\n` or `\r\n` if the original did
+pub fn restore_final_newline(sorted: String, original: &str) -> String {
+    if original.ends_with("\r\n") {
+        sorted + "\r\n"
+    } else if original.ends_with('\n') {
+        sorted + "\n"
+    } else {
+        sorted
+    }
+}
+
+/// Helper function to escape text for use in XML element content or attribute values.
+///
+/// Arguments:
+/// - `text`: the raw text
+///
+/// Returns:
+/// - The escaped text
+pub fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Helper function to turn XML text (entities and CDATA sections) back into raw text.
+///
+/// Arguments:
+/// - `text`: the XML text between two tags
+///
+/// Returns:
+/// - The raw text
+pub fn xml_unescape(text: &str) -> String {
+    let mut raw = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<![CDATA[") {
+        raw += &unescape_entities(&rest[..start]);
+        rest = &rest[start + "<![CDATA[".len()..];
+        match rest.find("]]>") {
+            Some(end) => {
+                raw += &rest[..end];
+                rest = &rest[end + "]]>".len()..];
+            }
+            None => {
+                raw += rest;
+                rest = "";
+            }
+        }
+    }
+    raw + &unescape_entities(rest)
+}
+
+/// Helper for `xml_unescape` to replace the predefined and numeric XML entities.
+fn unescape_entities(text: &str) -> String {
+    let mut raw = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        raw += &rest[..start];
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| &rest[1..end]);
+        let replacement = match entity {
+            Some("amp") => Some('&'),
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("quot") => Some('"'),
+            Some("apos") => Some('\''),
+            Some(code) if code.starts_with("#x") => u32::from_str_radix(&code[2..], 16)
+                .ok()
+                .and_then(char::from_u32),
+            Some(code) if code.starts_with('#') => {
+                code[1..].parse::<u32>().ok().and_then(char::from_u32)
+            }
+            _ => None,
+        };
+        match (replacement, entity) {
+            (Some(c), Some(entity)) => {
+                raw.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                raw.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    raw + rest
+}
+
 /// A generalized function to convert a JSON blob to a alphabetized JSON string.
 ///
 /// Arguments:
-/// - `table`: HashMap with the keys as Strings and the values (T) as a type that are De/Serialize-able
+/// - `table`: HashMap with the keys as Strings and the values (T) as snippets that are Serialize-able
+/// - `order`: the order to write the snippets in
 ///
 /// Returns:
 /// - Result of either the finished JSON string or an Error
-pub fn hash2ordered_string<T>(table: &HashMap<String, T>) -> Result<String, TektonError>
+pub fn hash2ordered_string<T>(
+    table: &HashMap<String, T>,
+    order: &SortOrder,
+) -> Result<String, TektonError>
 where
-    T: Serialize + for<'a> Deserialize<'a>,
+    T: SortFields,
 {
     match table.len() {
-        0 => Err(TektonError::Reason(
-            "Refusing to build string for 0 snippets".to_string(),
-        )),
+        0 => Err(TektonError::EmptyCollection { path: None }),
         _ => {
-            // 1. Get the keys
-            let mut keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
-
-            // 2. Sort alphabetically (all lowercase)
-            keys.sort_by_key(|a| a.to_lowercase());
-
-            // 3. Iterate over the HashMap based on the ordered keys and collect into the BTreeMap
-            let ordered: BTreeMap<String, _> = keys
+            // 1. Get the snippets
+            let snippets: Vec<(&str, &T)> = table
                 .iter()
-                .map(|key| {
-                    let snippet = table.get(key).unwrap();
-                    (key.clone(), snippet)
-                })
+                .map(|(name, snippet)| (name.as_str(), snippet))
                 .collect();
 
+            // 2. Sort them in the requested order
+            let snippets = order.sort(snippets);
+
+            // 3. Keep that order when serializing
+            let ordered = OrderedTable(snippets);
+
             // 4. Return the result as a JSON string
             match serde_json::to_string_pretty(&ordered) {
                 Ok(string) => Ok(string),
-                Err(e) => Err(TektonError::Reason(e.to_string())),
+                Err(e) => Err(TektonError::from(e)),
             }
         }
     }
 }
 
+/// A table of key and value pairs that is serialized as a JSON object in the order of its pairs
+struct OrderedTable<'a, T>(Vec<(&'a str, &'a T)>);
+
+impl<T: Serialize> Serialize for OrderedTable<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::get_filetype;
+    use super::{
+        backup_file, crawl_files, get_dirtype, get_filetype, hash2ordered_string, read_input,
+        sanitize_filename, write_to_file, xml_escape, xml_unescape,
+    };
+    use crate::errors::TektonError;
+    use crate::models::{collation::Collation, friendly::FriendlySnippetBody, sort::SortOrder};
+    use std::collections::HashMap;
+
+    fn ordered_keys(names: &[&str], collation: Collation) -> Vec<String> {
+        let table: HashMap<String, FriendlySnippetBody> = names
+            .iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    FriendlySnippetBody::new(None, Vec::new(), None),
+                )
+            })
+            .collect();
+        let order = SortOrder {
+            collation,
+            ..SortOrder::default()
+        };
+        // Every snippet starts with a line of `  "name": {` in the output
+        hash2ordered_string(&table, &order)
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.strip_prefix("  \""))
+            .map(|line| line.split('"').next().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_ordered_string_keeps_case_insensitive_order() {
+        assert_eq!(
+            ordered_keys(
+                &["Zeta", "alpha", "Beta", "gamma"],
+                Collation::CaseInsensitive
+            ),
+            vec!["alpha", "Beta", "gamma", "Zeta"]
+        );
+        assert_eq!(
+            ordered_keys(&["Zeta", "alpha", "Beta"], Collation::CaseSensitive),
+            vec!["Beta", "Zeta", "alpha"]
+        );
+    }
+
+    #[test]
+    fn test_ordered_string_with_non_ascii_names() {
+        assert_eq!(
+            ordered_keys(
+                &["zèbre", "Église", "eau", "snip10", "snip2"],
+                Collation::Unicode
+            ),
+            vec!["eau", "Église", "snip10", "snip2", "zèbre"]
+        );
+        assert_eq!(
+            ordered_keys(
+                &["zèbre", "Église", "eau", "snip10", "snip2"],
+                Collation::Natural
+            ),
+            vec!["eau", "snip2", "snip10", "zèbre", "Église"]
+        );
+    }
+
+    #[test]
+    fn test_read_input_reports_invalid_utf8_line() {
+        let file = std::env::temp_dir().join("tekton_invalid_utf8.snippet");
+        std::fs::write(&file, b"snippet ok\n\tfine\n\t\xff\n").unwrap();
+
+        let err = read_input(&file.to_string_lossy()).unwrap_err();
+        std::fs::remove_file(&file).unwrap();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "invalid UTF-8 on line 3");
+    }
+
+    #[test]
+    fn test_dirtype() {
+        assert_eq!(get_dirtype("example.json"), None);
+        assert_eq!(get_dirtype("snippets/rust-mode/"), Some("yasnippet"));
+        assert_eq!(get_dirtype("rust-mode"), Some("yasnippet"));
+        assert_eq!(get_dirtype("sublime/"), Some("sublime-snippet"));
+
+        let file = std::env::temp_dir().join("tekton_dirtype-mode");
+        std::fs::write(&file, "# name: not a directory").unwrap();
+        let dirtype = get_dirtype(&file.to_string_lossy()).map(String::from);
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(dirtype, None);
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("Print: a/b"), "Print_ a_b");
+        assert_eq!(sanitize_filename("..."), "_");
+    }
+
+    #[test]
+    fn test_xml_round_trip() {
+        let text = "<a href=\"x\">&amp;</a>";
+        assert_eq!(xml_unescape(&xml_escape(text)), text);
+    }
+
+    #[test]
+    fn test_xml_unescape_cdata_and_entities() {
+        let text = "&lt;<![CDATA[<raw> &amp;]]>&#65;&#x42;&unknown;";
+        assert_eq!(xml_unescape(text), "<<raw> &amp;AB&unknown;");
+    }
 
     #[test]
     fn test_empty_string_on_extension() {
@@ -197,4 +669,83 @@ mod tests {
         assert_ne!(result, None);
         assert_eq!(result.unwrap(), "json");
     }
+
+    #[test]
+    fn test_crawl_files_skips_ignored_and_non_snippet_files() {
+        let root = std::env::temp_dir().join("tekton_crawl_files");
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in [".git", ".vscode", "node_modules/pkg", "build", "snippets"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            ".gitignore",
+            ".git/config.json",
+            ".vscode/rust.code-snippets",
+            "node_modules/pkg/snippets.json",
+            "build/out.json",
+            "package.json",
+            "README.md",
+            "snippets/rust.json",
+            "snippets/go.json",
+            "snippets/python.snippets",
+            "snippets/notes.txt",
+        ] {
+            std::fs::write(root.join(file), "{}").unwrap();
+        }
+        std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+
+        let files: Vec<String> = crawl_files(root.to_string_lossy().to_string(), true)
+            .iter()
+            .map(|file| {
+                file.strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                ".vscode/rust.code-snippets",
+                "snippets/go.json",
+                "snippets/python.snippets",
+                "snippets/rust.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_to_file_replaces_and_backs_up() {
+        let dir = std::env::temp_dir().join("tekton_write_to_file");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("rust.json").to_string_lossy().to_string();
+        std::fs::write(&file, "old").unwrap();
+
+        backup_file(&file).unwrap();
+        write_to_file(file.clone(), "new".to_string()).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(
+            std::fs::read_to_string(format!("{}.bak", file)).unwrap(),
+            "old"
+        );
+        // Only the file and its backup are left, the temporary file was renamed
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_to_file_error() {
+        let missing = std::env::temp_dir().join("tekton_missing_dir/rust.json");
+
+        let result = write_to_file(missing.to_string_lossy().to_string(), "new".to_string());
+
+        assert!(matches!(
+            result,
+            Err(TektonError::Io { path: Some(path), .. }) if path.ends_with("rust.json")
+        ));
+    }
 }